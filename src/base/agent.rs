@@ -0,0 +1,23 @@
+use peroxide::fuga::*;
+
+/// Interaction lifecycle of a learning agent
+///
+/// A training loop calls `begin_episode`, then repeatedly `select_action` and `observe`,
+/// and finally `end_episode`. `next_state = None` in `observe` marks a terminal transition;
+/// an episode cut off by a step cap ends with `end_episode` after a non-terminal `observe`.
+/// All randomness is drawn from the `rng` supplied by the caller.
+pub trait Agent<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A>;
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    );
+    fn begin_episode(&mut self) {}
+    fn end_episode(&mut self) {}
+    /// Switch between greedy evaluation (`true`) and exploratory learning (`false`)
+    fn set_greedy(&mut self, greedy: bool);
+}
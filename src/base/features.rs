@@ -0,0 +1,487 @@
+use super::function::FeatureExtractor;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::hash::{Hash, Hasher};
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Continuous Coordinates
+// └──────────────────────────────────────────────────────────┘
+/// State viewed as a point in R^d
+pub trait Coordinates {
+    fn coordinates(&self) -> Vec<f64>;
+}
+
+impl Coordinates for Vec<f64> {
+    fn coordinates(&self) -> Vec<f64> {
+        self.clone()
+    }
+}
+
+impl<const N: usize> Coordinates for [f64; N] {
+    fn coordinates(&self) -> Vec<f64> {
+        self.to_vec()
+    }
+}
+
+impl Coordinates for (f64, f64) {
+    fn coordinates(&self) -> Vec<f64> {
+        vec![self.0, self.1]
+    }
+}
+
+/// Grid cells as integer coordinates
+impl Coordinates for (usize, usize) {
+    fn coordinates(&self) -> Vec<f64> {
+        vec![self.0 as f64, self.1 as f64]
+    }
+}
+
+/// Axis-aligned box [low, high] of the state space
+#[derive(Debug, Clone)]
+pub struct StateRange {
+    low: Vec<f64>,
+    high: Vec<f64>,
+}
+
+impl StateRange {
+    pub fn new(low: Vec<f64>, high: Vec<f64>) -> Self {
+        assert_eq!(low.len(), high.len(), "Bound length mismatch");
+        assert!(
+            low.iter().zip(high.iter()).all(|(l, h)| l < h),
+            "Every lower bound must be below its upper bound"
+        );
+        StateRange { low, high }
+    }
+
+    pub fn get_low(&self) -> &[f64] {
+        &self.low
+    }
+
+    pub fn get_high(&self) -> &[f64] {
+        &self.high
+    }
+
+    pub fn dim(&self) -> usize {
+        self.low.len()
+    }
+
+    /// Map `x` into [0, 1]^d (coordinates outside the range are clamped)
+    pub fn normalize(&self, x: &[f64]) -> Vec<f64> {
+        assert_eq!(x.len(), self.dim(), "State dimension mismatch");
+        x.iter()
+            .zip(self.low.iter().zip(self.high.iter()))
+            .map(|(x, (l, h))| ((x - l) / (h - l)).clamp(0f64, 1f64))
+            .collect()
+    }
+}
+
+/// All multi-indices in {0, ..., n}^d (first coordinate varies slowest)
+fn multi_indices(n: usize, d: usize) -> Vec<Vec<usize>> {
+    (0..d).fold(vec![vec![]], |acc, _| {
+        acc.into_iter()
+            .flat_map(|c| {
+                (0..=n).map(move |i| {
+                    let mut c = c.clone();
+                    c.push(i);
+                    c
+                })
+            })
+            .collect()
+    })
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Tile Coding
+// └──────────────────────────────────────────────────────────┘
+/// Index hash table: assigns consecutive indices to tile coordinates as they are first seen
+///
+/// Once `size` indices are taken, new coordinates are hashed into [0, size) and may collide;
+/// such collisions are counted in `get_overflow_count`.
+#[derive(Debug, Clone)]
+pub struct IndexHashTable {
+    size: usize,
+    table: HashMap<Vec<i64>, usize>,
+    overflow_count: usize,
+}
+
+impl IndexHashTable {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Size must be positive");
+        IndexHashTable {
+            size,
+            table: HashMap::new(),
+            overflow_count: 0,
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of distinct coordinates holding their own index
+    pub fn count(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.table.len() >= self.size
+    }
+
+    pub fn get_overflow_count(&self) -> usize {
+        self.overflow_count
+    }
+
+    pub fn get_index(&mut self, coords: &[i64]) -> usize {
+        if let Some(&i) = self.table.get(coords) {
+            return i;
+        }
+        if self.is_full() {
+            self.overflow_count += 1;
+            let mut hasher = DefaultHasher::new();
+            coords.hash(&mut hasher);
+            return (hasher.finish() % self.size as u64) as usize;
+        }
+        let i = self.table.len();
+        self.table.insert(coords.to_vec(), i);
+        i
+    }
+}
+
+/// Multi-tiling tile coding over a `StateRange`
+///
+/// Each of the `num_tilings` tilings splits every normalized dimension into `tiles_per_dim`
+/// intervals and is offset by asymmetric displacements (1, 3, 5, ...) / `num_tilings` of a
+/// tile width. The features are binary with exactly `num_tilings` ones; tile coordinates are
+/// mapped to indices through an `IndexHashTable` of `size` entries.
+#[derive(Debug, Clone)]
+pub struct TileCoding {
+    range: StateRange,
+    num_tilings: usize,
+    tiles_per_dim: usize,
+    iht: RefCell<IndexHashTable>,
+}
+
+impl TileCoding {
+    pub fn new(range: StateRange, num_tilings: usize, tiles_per_dim: usize, size: usize) -> Self {
+        assert!(num_tilings >= 1, "num_tilings must be at least 1");
+        assert!(tiles_per_dim >= 1, "tiles_per_dim must be at least 1");
+        TileCoding {
+            range,
+            num_tilings,
+            tiles_per_dim,
+            iht: RefCell::new(IndexHashTable::new(size)),
+        }
+    }
+
+    pub fn get_range(&self) -> &StateRange {
+        &self.range
+    }
+
+    pub fn get_num_tilings(&self) -> usize {
+        self.num_tilings
+    }
+
+    pub fn get_tiles_per_dim(&self) -> usize {
+        self.tiles_per_dim
+    }
+
+    /// Snapshot of the index hash table
+    pub fn get_iht(&self) -> IndexHashTable {
+        self.iht.borrow().clone()
+    }
+
+    /// Indices of the active tile in every tiling
+    pub fn active_tiles(&self, x: &[f64]) -> Vec<usize> {
+        let n = self.num_tilings as i64;
+        let q = self
+            .range
+            .normalize(x)
+            .iter()
+            .map(|x| (x * self.tiles_per_dim as f64 * n as f64).floor() as i64)
+            .collect::<Vec<_>>();
+        let mut iht = self.iht.borrow_mut();
+        (0..n)
+            .map(|tiling| {
+                let mut coords = Vec::with_capacity(q.len() + 1);
+                coords.push(tiling);
+                let mut b = tiling;
+                for q in q.iter() {
+                    coords.push((q + b).div_euclid(n));
+                    b += 2 * tiling;
+                }
+                iht.get_index(&coords)
+            })
+            .collect()
+    }
+}
+
+impl<S: Coordinates> FeatureExtractor<S> for TileCoding {
+    fn num_features(&self) -> usize {
+        self.iht.borrow().get_size()
+    }
+
+    fn features(&self, state: &S) -> Vec<f64> {
+        let mut x = vec![0f64; self.iht.borrow().get_size()];
+        for i in self.active_tiles(&state.coordinates()) {
+            x[i] = 1f64;
+        }
+        x
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Radial Basis Functions
+// └──────────────────────────────────────────────────────────┘
+/// Gaussian RBFs exp(-‖x - c_i‖² / 2σ²) on the normalized state
+#[derive(Debug, Clone)]
+pub struct RadialBasis {
+    range: StateRange,
+    centers: Vec<Vec<f64>>,
+    sigma: f64,
+}
+
+impl RadialBasis {
+    /// `centers` are given in normalized coordinates
+    pub fn new(range: StateRange, centers: Vec<Vec<f64>>, sigma: f64) -> Self {
+        assert!(sigma > 0f64, "sigma must be positive");
+        assert!(
+            centers.iter().all(|c| c.len() == range.dim()),
+            "Center dimension mismatch"
+        );
+        RadialBasis {
+            range,
+            centers,
+            sigma,
+        }
+    }
+
+    /// `centers_per_dim` evenly spaced centers per dimension, including 0 and 1
+    pub fn grid(range: StateRange, centers_per_dim: usize, sigma: f64) -> Self {
+        assert!(centers_per_dim >= 2, "centers_per_dim must be at least 2");
+        let h = 1f64 / (centers_per_dim - 1) as f64;
+        let centers = multi_indices(centers_per_dim - 1, range.dim())
+            .into_iter()
+            .map(|c| c.into_iter().map(|i| i as f64 * h).collect())
+            .collect();
+        RadialBasis::new(range, centers, sigma)
+    }
+
+    pub fn get_range(&self) -> &StateRange {
+        &self.range
+    }
+
+    pub fn get_centers(&self) -> &[Vec<f64>] {
+        &self.centers
+    }
+
+    pub fn get_sigma(&self) -> f64 {
+        self.sigma
+    }
+}
+
+impl<S: Coordinates> FeatureExtractor<S> for RadialBasis {
+    fn num_features(&self) -> usize {
+        self.centers.len()
+    }
+
+    fn features(&self, state: &S) -> Vec<f64> {
+        let x = self.range.normalize(&state.coordinates());
+        let two_sigma_sq = 2f64 * self.sigma * self.sigma;
+        self.centers
+            .iter()
+            .map(|c| {
+                let d2 = x
+                    .iter()
+                    .zip(c.iter())
+                    .map(|(x, c)| (x - c).powi(2))
+                    .sum::<f64>();
+                (-d2 / two_sigma_sq).exp()
+            })
+            .collect()
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Fourier Basis
+// └──────────────────────────────────────────────────────────┘
+/// Order-n Fourier cosine basis cos(π cᵀx) over c ∈ {0, ..., n}^d on the normalized state
+///
+/// There are (n + 1)^d features; c = 0 gives the constant feature.
+#[derive(Debug, Clone)]
+pub struct FourierBasis {
+    range: StateRange,
+    order: usize,
+    coefficients: Vec<Vec<usize>>,
+}
+
+impl FourierBasis {
+    pub fn new(range: StateRange, order: usize) -> Self {
+        let coefficients = multi_indices(order, range.dim());
+        FourierBasis {
+            range,
+            order,
+            coefficients,
+        }
+    }
+
+    pub fn get_range(&self) -> &StateRange {
+        &self.range
+    }
+
+    pub fn get_order(&self) -> usize {
+        self.order
+    }
+
+    pub fn get_coefficients(&self) -> &[Vec<usize>] {
+        &self.coefficients
+    }
+}
+
+impl<S: Coordinates> FeatureExtractor<S> for FourierBasis {
+    fn num_features(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    fn features(&self, state: &S) -> Vec<f64> {
+        let x = self.range.normalize(&state.coordinates());
+        self.coefficients
+            .iter()
+            .map(|c| {
+                let cx = c
+                    .iter()
+                    .zip(x.iter())
+                    .map(|(c, x)| *c as f64 * x)
+                    .sum::<f64>();
+                (PI * cx).cos()
+            })
+            .collect()
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Polynomial Basis
+// └──────────────────────────────────────────────────────────┘
+/// Order-n polynomial basis Π x_j^(c_j) over c ∈ {0, ..., n}^d on the normalized state
+///
+/// There are (n + 1)^d features; c = 0 gives the constant feature.
+#[derive(Debug, Clone)]
+pub struct PolynomialBasis {
+    range: StateRange,
+    order: usize,
+    exponents: Vec<Vec<usize>>,
+}
+
+impl PolynomialBasis {
+    pub fn new(range: StateRange, order: usize) -> Self {
+        let exponents = multi_indices(order, range.dim());
+        PolynomialBasis {
+            range,
+            order,
+            exponents,
+        }
+    }
+
+    pub fn get_range(&self) -> &StateRange {
+        &self.range
+    }
+
+    pub fn get_order(&self) -> usize {
+        self.order
+    }
+
+    pub fn get_exponents(&self) -> &[Vec<usize>] {
+        &self.exponents
+    }
+}
+
+impl<S: Coordinates> FeatureExtractor<S> for PolynomialBasis {
+    fn num_features(&self) -> usize {
+        self.exponents.len()
+    }
+
+    fn features(&self, state: &S) -> Vec<f64> {
+        let x = self.range.normalize(&state.coordinates());
+        self.exponents
+            .iter()
+            .map(|c| {
+                c.iter()
+                    .zip(x.iter())
+                    .map(|(c, x)| x.powi(*c as i32))
+                    .product()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_square() -> StateRange {
+        StateRange::new(vec![0.0, 0.0], vec![1.0, 1.0])
+    }
+
+    #[test]
+    fn index_hash_table_overflow() {
+        let mut iht = IndexHashTable::new(2);
+        assert_eq!(iht.get_index(&[0, 0]), 0);
+        assert_eq!(iht.get_index(&[0, 1]), 1);
+        assert_eq!(iht.get_index(&[0, 0]), 0);
+        assert!(iht.is_full());
+        assert_eq!(iht.get_overflow_count(), 0);
+
+        let i = iht.get_index(&[1, 1]);
+        assert!(i < 2);
+        assert_eq!(iht.get_index(&[1, 1]), i);
+        assert_eq!(iht.count(), 2);
+        assert_eq!(iht.get_overflow_count(), 2);
+    }
+
+    #[test]
+    fn tile_coding_activates_one_tile_per_tiling() {
+        let tiles = TileCoding::new(unit_square(), 8, 4, 4096);
+        for x in [[0.0, 0.0], [0.3, 0.7], [1.0, 1.0], [2.0, -1.0]] {
+            let active = tiles.active_tiles(&x);
+            assert_eq!(active.len(), 8);
+            let mut distinct = active.clone();
+            distinct.sort();
+            distinct.dedup();
+            assert_eq!(distinct.len(), 8, "{:?}", x);
+
+            let features = tiles.features(&x);
+            assert_eq!(features.len(), 4096);
+            assert_eq!(features.iter().sum::<f64>(), 8.0);
+        }
+        assert_eq!(
+            tiles.active_tiles(&[0.3, 0.7]),
+            tiles.active_tiles(&[0.3, 0.7])
+        );
+        // Clamped to the corner
+        assert_eq!(
+            tiles.active_tiles(&[2.0, -1.0]),
+            tiles.active_tiles(&[1.0, 0.0])
+        );
+        assert_eq!(tiles.get_iht().get_overflow_count(), 0);
+    }
+
+    #[test]
+    fn basis_sizes_and_constant_feature() {
+        let fourier = FourierBasis::new(unit_square(), 3);
+        let features = fourier.features(&[0.2, 0.9]);
+        assert_eq!(features.len(), 16);
+        assert_eq!(features[0], 1.0);
+
+        let poly = PolynomialBasis::new(unit_square(), 2);
+        let features = poly.features(&[0.5, 0.25]);
+        assert_eq!(features.len(), 9);
+        assert_eq!(features[0], 1.0);
+        // Exponents (2, 2)
+        assert_eq!(features[8], 0.25 * 0.0625);
+
+        let rbf = RadialBasis::grid(unit_square(), 3, 0.5);
+        assert_eq!(rbf.get_centers().len(), 9);
+        assert_eq!(rbf.features(&[0.5, 0.5])[4], 1.0);
+    }
+}
@@ -1,3 +1,8 @@
+use crate::base::process::MarkovDecisionProcess;
+use peroxide::fuga::*;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
 pub trait ValueFunction<S> {
     fn value(&self, state: &S) -> f64;
 }
@@ -5,3 +10,373 @@ pub trait ValueFunction<S> {
 pub trait ActionValueFunction<S, A> {
     fn value(&self, state: &S, action: &A) -> f64;
 }
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Q-Table
+// └──────────────────────────────────────────────────────────┘
+/// Tabular action-value function
+///
+/// Actions are kept in insertion order per state, so greedy tie-breaking and
+/// iteration are reproducible.
+#[derive(Debug, Clone)]
+pub struct QTable<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    table: HashMap<S, Vec<(A, f64)>>,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Default for QTable<S, A> {
+    fn default() -> Self {
+        QTable::new()
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> QTable<S, A> {
+    pub fn new() -> Self {
+        QTable {
+            table: HashMap::new(),
+        }
+    }
+
+    /// Q-table with every `(s, a)` of `mdp` set to `init`
+    pub fn from_mdp<M: MarkovDecisionProcess<S, A>>(mdp: &M, init: f64) -> Self {
+        let mut table = HashMap::new();
+        for s in mdp.states() {
+            let entries = mdp.actions_at(&s).into_iter().map(|a| (a, init)).collect();
+            table.insert(s, entries);
+        }
+        QTable { table }
+    }
+
+    pub fn get_table(&self) -> &HashMap<S, Vec<(A, f64)>> {
+        &self.table
+    }
+
+    pub fn get_value(&self, state: &S, action: &A) -> Option<f64> {
+        self.table
+            .get(state)
+            .and_then(|entries| entries.iter().find(|(a, _)| a == action))
+            .map(|(_, q)| *q)
+    }
+
+    pub fn update_value(&mut self, state: &S, action: &A, value: f64) {
+        let entries = self.table.entry(state.clone()).or_default();
+        match entries.iter_mut().find(|(a, _)| a == action) {
+            Some((_, q)) => *q = value,
+            None => entries.push((action.clone(), value)),
+        }
+    }
+
+    /// Multiply every entry by `c` (e.g. decaying eligibility traces)
+    pub fn scale(&mut self, c: f64) {
+        self.table
+            .values_mut()
+            .flat_map(|entries| entries.iter_mut())
+            .for_each(|(_, q)| *q *= c);
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// Known `(action, value)` pairs at `state`
+    pub fn action_values(&self, state: &S) -> &[(A, f64)] {
+        self.table.get(state).map(|e| e.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn actions_at(&self, state: &S) -> Vec<A> {
+        self.action_values(state)
+            .iter()
+            .map(|(a, _)| a.clone())
+            .collect()
+    }
+
+    /// max_a Q(s, a) (0 for unknown or action-less states)
+    pub fn max_value(&self, state: &S) -> f64 {
+        self.action_values(state)
+            .iter()
+            .map(|(_, q)| *q)
+            .fold(None, |acc: Option<f64>, q| {
+                Some(acc.map_or(q, |m| m.max(q)))
+            })
+            .unwrap_or(0.0)
+    }
+
+    /// All actions attaining max_a Q(s, a)
+    pub fn greedy_actions(&self, state: &S) -> Vec<A> {
+        greedy_actions(self.action_values(state))
+    }
+
+    /// ε-greedy distribution over the actions at `state`
+    ///
+    /// Each action gets ε/|A| and the greedy mass (1-ε) is split uniformly over ties.
+    pub fn epsilon_greedy_probs(&self, state: &S, epsilon: f64) -> Vec<(A, f64)> {
+        epsilon_greedy_probs(self.action_values(state), epsilon)
+    }
+
+    /// Sample an ε-greedy action (ties broken uniformly at random)
+    pub fn epsilon_greedy_action(
+        &self,
+        state: &S,
+        epsilon: f64,
+        rng: &mut dyn RngCore,
+    ) -> Option<A> {
+        epsilon_greedy_action(self.action_values(state), epsilon, rng)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValueFunction<S, A>
+    for QTable<S, A>
+{
+    fn value(&self, state: &S, action: &A) -> f64 {
+        self.get_value(state, action).unwrap_or(0.0)
+    }
+}
+
+/// Actions attaining the maximum of `(action, q)` pairs
+fn greedy_actions<A: Clone>(values: &[(A, f64)]) -> Vec<A> {
+    let max_value = values
+        .iter()
+        .map(|(_, q)| *q)
+        .fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .filter(|(_, q)| *q == max_value)
+        .map(|(a, _)| a.clone())
+        .collect()
+}
+
+/// ε-greedy distribution over `(action, q)` pairs
+///
+/// Each action gets ε/|A| and the greedy mass (1-ε) is split uniformly over ties.
+fn epsilon_greedy_probs<A: Clone>(values: &[(A, f64)], epsilon: f64) -> Vec<(A, f64)> {
+    let max_value = values
+        .iter()
+        .map(|(_, q)| *q)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let n = values.len() as f64;
+    let k = values.iter().filter(|(_, q)| *q == max_value).count() as f64;
+    values
+        .iter()
+        .map(|(a, q)| {
+            let p_greedy = if *q == max_value {
+                (1f64 - epsilon) / k
+            } else {
+                0f64
+            };
+            (a.clone(), epsilon / n + p_greedy)
+        })
+        .collect()
+}
+
+/// Sample an ε-greedy action from `(action, q)` pairs (ties broken uniformly at random)
+fn epsilon_greedy_action<A: Clone>(
+    values: &[(A, f64)],
+    epsilon: f64,
+    rng: &mut dyn RngCore,
+) -> Option<A> {
+    if rng.gen::<f64>() < epsilon {
+        values.iter().map(|(a, _)| a.clone()).choose(rng)
+    } else {
+        greedy_actions(values).into_iter().choose(rng)
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Feature Extraction
+// └──────────────────────────────────────────────────────────┘
+/// Maps a state to a fixed-length feature vector x(s)
+pub trait FeatureExtractor<S> {
+    fn num_features(&self) -> usize;
+    fn features(&self, state: &S) -> Vec<f64>;
+}
+
+/// One-hot features over an enumerated state set (the tabular case as a linear approximator)
+///
+/// Unknown states map to the zero vector.
+#[derive(Debug, Clone)]
+pub struct TabularFeatures<S: Eq + std::hash::Hash + Clone> {
+    index: HashMap<S, usize>,
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TabularFeatures<S> {
+    pub fn new(states: Vec<S>) -> Self {
+        let index = states
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| (s, i))
+            .collect();
+        TabularFeatures { index }
+    }
+
+    pub fn from_mdp<A, M: MarkovDecisionProcess<S, A>>(mdp: &M) -> Self {
+        TabularFeatures::new(mdp.states())
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> FeatureExtractor<S> for TabularFeatures<S> {
+    fn num_features(&self) -> usize {
+        self.index.len()
+    }
+
+    fn features(&self, state: &S) -> Vec<f64> {
+        let mut x = vec![0f64; self.index.len()];
+        if let Some(&i) = self.index.get(state) {
+            x[i] = 1f64;
+        }
+        x
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Linear Value Function
+// └──────────────────────────────────────────────────────────┘
+/// v(s, w) = wᵀ x(s)
+#[derive(Debug, Clone)]
+pub struct LinearValueFunction<S, F: FeatureExtractor<S>> {
+    features: F,
+    weights: Vec<f64>,
+    state_type: PhantomData<S>,
+}
+
+impl<S, F: FeatureExtractor<S>> LinearValueFunction<S, F> {
+    /// Every weight set to `init`
+    pub fn new(features: F, init: f64) -> Self {
+        let weights = vec![init; features.num_features()];
+        LinearValueFunction {
+            features,
+            weights,
+            state_type: PhantomData,
+        }
+    }
+
+    pub fn get_features(&self) -> &F {
+        &self.features
+    }
+
+    pub fn get_weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn set_weights(&mut self, weights: Vec<f64>) {
+        assert_eq!(weights.len(), self.weights.len(), "Weight length mismatch");
+        self.weights = weights;
+    }
+
+    /// ∇_w v(s, w) = x(s)
+    pub fn gradient(&self, state: &S) -> Vec<f64> {
+        self.features.features(state)
+    }
+
+    /// w <- w + step ∇_w v(s, w)
+    pub fn update(&mut self, state: &S, step: f64) {
+        let x = self.gradient(state);
+        self.weights = self.weights.add_vec(&x.mul_scalar(step));
+    }
+}
+
+impl<S, F: FeatureExtractor<S>> ValueFunction<S> for LinearValueFunction<S, F> {
+    fn value(&self, state: &S) -> f64 {
+        self.features.features(state).dot(&self.weights)
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Linear Action-Value Function
+// └──────────────────────────────────────────────────────────┘
+/// q(s, a, w) = w_aᵀ x(s) with one weight block per action
+///
+/// Actions outside the list given at construction have value 0 and cannot be updated.
+#[derive(Debug, Clone)]
+pub struct LinearActionValueFunction<S, A: Clone + PartialEq, F: FeatureExtractor<S>> {
+    features: F,
+    actions: Vec<A>,
+    weights: Vec<Vec<f64>>,
+    state_type: PhantomData<S>,
+}
+
+impl<S, A: Clone + PartialEq, F: FeatureExtractor<S>> LinearActionValueFunction<S, A, F> {
+    /// Every weight set to `init`
+    pub fn new(features: F, actions: Vec<A>, init: f64) -> Self {
+        let weights = vec![vec![init; features.num_features()]; actions.len()];
+        LinearActionValueFunction {
+            features,
+            actions,
+            weights,
+            state_type: PhantomData,
+        }
+    }
+
+    pub fn get_features(&self) -> &F {
+        &self.features
+    }
+
+    pub fn get_actions(&self) -> &[A] {
+        &self.actions
+    }
+
+    /// Weight block of `action`
+    pub fn get_weights(&self, action: &A) -> Option<&[f64]> {
+        self.action_index(action)
+            .map(|i| self.weights[i].as_slice())
+    }
+
+    /// ∇_{w_a} q(s, a, w) = x(s)
+    pub fn gradient(&self, state: &S) -> Vec<f64> {
+        self.features.features(state)
+    }
+
+    /// w_a <- w_a + step ∇_{w_a} q(s, a, w)
+    pub fn update(&mut self, state: &S, action: &A, step: f64) {
+        let i = self.action_index(action).expect("Unknown action");
+        let x = self.gradient(state);
+        self.weights[i] = self.weights[i].add_vec(&x.mul_scalar(step));
+    }
+
+    /// `(action, q(s, a))` for each of `actions` (x(s) is computed once)
+    pub fn action_values(&self, state: &S, actions: &[A]) -> Vec<(A, f64)> {
+        let x = self.features.features(state);
+        actions
+            .iter()
+            .map(|a| {
+                let q = self
+                    .action_index(a)
+                    .map_or(0f64, |i| x.dot(&self.weights[i]));
+                (a.clone(), q)
+            })
+            .collect()
+    }
+
+    /// All of `actions` attaining max_a q(s, a)
+    pub fn greedy_actions(&self, state: &S, actions: &[A]) -> Vec<A> {
+        greedy_actions(&self.action_values(state, actions))
+    }
+
+    /// ε-greedy distribution over `actions` (greedy mass split uniformly over ties)
+    pub fn epsilon_greedy_probs(&self, state: &S, actions: &[A], epsilon: f64) -> Vec<(A, f64)> {
+        epsilon_greedy_probs(&self.action_values(state, actions), epsilon)
+    }
+
+    /// Sample an ε-greedy action among `actions` (ties broken uniformly at random)
+    pub fn epsilon_greedy_action(
+        &self,
+        state: &S,
+        actions: &[A],
+        epsilon: f64,
+        rng: &mut dyn RngCore,
+    ) -> Option<A> {
+        epsilon_greedy_action(&self.action_values(state, actions), epsilon, rng)
+    }
+
+    fn action_index(&self, action: &A) -> Option<usize> {
+        self.actions.iter().position(|a| a == action)
+    }
+}
+
+impl<S, A: Clone + PartialEq, F: FeatureExtractor<S>> ActionValueFunction<S, A>
+    for LinearActionValueFunction<S, A, F>
+{
+    fn value(&self, state: &S, action: &A) -> f64 {
+        self.action_index(action).map_or(0f64, |i| {
+            self.features.features(state).dot(&self.weights[i])
+        })
+    }
+}
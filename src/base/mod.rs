@@ -1,3 +1,5 @@
+pub mod agent;
+pub mod features;
 pub mod function;
 pub mod policy;
 pub mod process;
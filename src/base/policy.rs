@@ -4,7 +4,30 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 
 pub trait Policy<S, A> {
-    fn gen_action(&self, state: &S) -> Option<A>;
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A>;
+
+    /// π(·|s) as `(action, probability)` pairs
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)>;
+
+    /// π(a|s) (0 for actions not listed by `action_probs`)
+    fn prob(&self, state: &S, action: &A) -> f64
+    where
+        A: PartialEq,
+    {
+        self.action_probs(state)
+            .into_iter()
+            .filter(|(a, _)| a == action)
+            .map(|(_, p)| p)
+            .sum()
+    }
+
+    /// log π(a|s) (-∞ for actions with zero probability)
+    fn log_prob(&self, state: &S, action: &A) -> f64
+    where
+        A: PartialEq,
+    {
+        self.prob(state, action).ln()
+    }
 }
 
 // ┌──────────────────────────────────────────────────────────┐
@@ -35,43 +58,57 @@ impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S,
     pub fn get_value_function(&self) -> &HashMap<S, f64> {
         &self.value_function
     }
-}
 
-impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S, A>> Policy<S, A>
-    for GreedyValuePolicy<'a, S, A, M>
-{
-    fn gen_action(&self, state: &S) -> Option<A> {
+    /// Actions at `state`, flagged when their successor has the maximal value
+    fn greedy_mask(&self, state: &S) -> Vec<(A, bool)> {
         let mdp = self.get_mdp();
         let v = self.get_value_function();
         let actions = mdp.actions_at(state);
-        if actions.is_empty() {
-            return None;
-        }
+        let values = actions
+            .iter()
+            .map(|a| {
+                *mdp.transition(state, a)
+                    .and_then(|s| v.get(&s))
+                    .unwrap_or(&0.0)
+            })
+            .collect::<Vec<f64>>();
 
         // 1. Find max value
-        let mut max_value = std::f64::MIN;
-        for a in actions.iter() {
-            let value = mdp
-                .transition(state, a)
-                .and_then(|s| v.get(&s))
-                .unwrap_or(&0.0);
-            max_value = max_value.max(*value);
-        }
+        let max_value = values.iter().fold(f64::MIN, |m, v| m.max(*v));
 
         // 2. Find max action
-        let mut max_action = vec![];
-        for a in actions.iter() {
-            let value = mdp
-                .transition(state, a)
-                .and_then(|s| v.get(&s))
-                .unwrap_or(&0.0);
-            if value == &max_value {
-                max_action.push(a.clone());
-            }
-        }
+        actions
+            .into_iter()
+            .zip(values)
+            .map(|(a, value)| (a, value == max_value))
+            .collect()
+    }
+
+    /// All actions whose successor has the maximal value
+    pub fn greedy_actions(&self, state: &S) -> Vec<A> {
+        self.greedy_mask(state)
+            .into_iter()
+            .filter(|(_, is_max)| *is_max)
+            .map(|(a, _)| a)
+            .collect()
+    }
+}
 
+impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S, A>> Policy<S, A>
+    for GreedyValuePolicy<'a, S, A, M>
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
         // 3. Choose random action
-        Some(max_action.into_iter().choose(&mut thread_rng()).unwrap())
+        self.greedy_actions(state).into_iter().choose(rng)
+    }
+
+    /// Ties share the probability mass uniformly
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let mask = self.greedy_mask(state);
+        let k = mask.iter().filter(|(_, is_max)| *is_max).count() as f64;
+        mask.into_iter()
+            .map(|(a, is_max)| (a, if is_max { 1f64 / k } else { 0f64 }))
+            .collect()
     }
 }
 
@@ -82,7 +119,7 @@ pub struct EpsilonGreedyValuePolicy<'a, S, A, M: MarkovDecisionProcess<S, A>> {
     mdp: &'a M,
     value_function: HashMap<S, f64>,
     action_type: PhantomData<A>,
-    _bernoulli: OPDist<f64>,
+    epsilon: f64,
     _random: bool,
 }
 
@@ -90,12 +127,11 @@ impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S,
     EpsilonGreedyValuePolicy<'a, S, A, M>
 {
     pub fn new(mdp: &'a M, value_function: HashMap<S, f64>, epsilon: f64) -> Self {
-        let bernoulli = Bernoulli(epsilon);
         EpsilonGreedyValuePolicy {
             mdp,
             value_function,
             action_type: PhantomData,
-            _bernoulli: bernoulli,
+            epsilon,
             _random: true,
         }
     }
@@ -112,21 +148,39 @@ impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S,
     pub fn turn_off_random(&mut self) {
         self._random = false;
     }
-}
 
-impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S, A>> Policy<S, A>
-    for EpsilonGreedyValuePolicy<'a, S, A, M>
-{
-    fn gen_action(&self, state: &S) -> Option<A> {
-        let sample = self._bernoulli.sample(1)[0];
-        let sample = sample > 0f64;
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    /// `gen_action` on `value_function` instead of the stored copy
+    pub fn gen_action_over(
+        &self,
+        value_function: &HashMap<S, f64>,
+        state: &S,
+        rng: &mut dyn RngCore,
+    ) -> Option<A> {
+        let sample = rng.gen::<f64>() < self.epsilon;
 
         let mdp = self.get_mdp();
-        let v = self.get_value_function();
+        let actions = mdp.actions_at(state);
         if sample && self._random {
-            mdp.actions_at(state).into_iter().choose(&mut thread_rng())
+            actions.into_iter().choose(rng)
         } else {
-            mdp.actions_at(state).into_iter().max_by(|a, b| {
+            self.greedy_index(value_function, state, &actions)
+                .map(|i| actions[i].clone())
+        }
+    }
+
+    /// Index of the greedy action on `v` in `actions_at(state)`
+    ///
+    /// `max_by` keeps the last of several maximal elements, so ties go to the last action.
+    fn greedy_index(&self, v: &HashMap<S, f64>, state: &S, actions: &[A]) -> Option<usize> {
+        let mdp = self.get_mdp();
+        actions
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
                 let value_a = mdp
                     .transition(state, a)
                     .and_then(|s| v.get(&s))
@@ -139,6 +193,179 @@ impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S,
                     .partial_cmp(value_b)
                     .unwrap_or(std::cmp::Ordering::Equal)
             })
+            .map(|(i, _)| i)
+    }
+}
+
+impl<'a, S: Eq + std::hash::Hash + Clone, A: Clone, M: MarkovDecisionProcess<S, A>> Policy<S, A>
+    for EpsilonGreedyValuePolicy<'a, S, A, M>
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action_over(self.get_value_function(), state, rng)
+    }
+
+    /// ε/|A| for every action plus 1-ε on the greedy one (ε = 0 with randomness turned off)
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let actions = self.get_mdp().actions_at(state);
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        let n = actions.len() as f64;
+        let greedy = self.greedy_index(self.get_value_function(), state, &actions);
+        actions
+            .into_iter()
+            .enumerate()
+            .map(|(i, a)| {
+                let p_greedy = if Some(i) == greedy {
+                    1f64 - epsilon
+                } else {
+                    0f64
+                };
+                (a, epsilon / n + p_greedy)
+            })
+            .collect()
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Uniform Random Policy
+// └──────────────────────────────────────────────────────────┘
+/// Picks uniformly among `actions_at(state)` (typical behavior policy for off-policy methods)
+pub struct UniformRandomPolicy<'a, S, A, M: MarkovDecisionProcess<S, A>> {
+    mdp: &'a M,
+    state_type: PhantomData<S>,
+    action_type: PhantomData<A>,
+}
+
+impl<'a, S, A, M: MarkovDecisionProcess<S, A>> UniformRandomPolicy<'a, S, A, M> {
+    pub fn new(mdp: &'a M) -> Self {
+        UniformRandomPolicy {
+            mdp,
+            state_type: PhantomData,
+            action_type: PhantomData,
         }
     }
+
+    pub fn get_mdp(&self) -> &M {
+        self.mdp
+    }
+}
+
+impl<'a, S, A, M: MarkovDecisionProcess<S, A>> Policy<S, A> for UniformRandomPolicy<'a, S, A, M> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.mdp.actions_at(state).into_iter().choose(rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let actions = self.mdp.actions_at(state);
+        let p = 1f64 / actions.len() as f64;
+        actions.into_iter().map(|a| (a, p)).collect()
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Tabular Policy (Deterministic)
+// └──────────────────────────────────────────────────────────┘
+#[derive(Debug, Clone)]
+pub struct TabularPolicy<S: Eq + std::hash::Hash + Clone, A: Clone> {
+    policy: HashMap<S, A>,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone> TabularPolicy<S, A> {
+    pub fn new(policy: HashMap<S, A>) -> Self {
+        TabularPolicy { policy }
+    }
+
+    pub fn get_action(&self, state: &S) -> Option<&A> {
+        self.policy.get(state)
+    }
+
+    pub fn get_policy_map(&self) -> &HashMap<S, A> {
+        &self.policy
+    }
+
+    pub fn update_action(&mut self, state: &S, action: A) {
+        self.policy.insert(state.clone(), action);
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone> Policy<S, A> for TabularPolicy<S, A> {
+    fn gen_action(&self, state: &S, _rng: &mut dyn RngCore) -> Option<A> {
+        self.get_action(state).cloned()
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        self.get_action(state)
+            .map(|a| vec![(a.clone(), 1f64)])
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// States 0..=3 on a line; actions move one cell left (-1) or right (+1), 3 is terminal
+    struct Line;
+
+    impl MarkovDecisionProcess<i32, i32> for Line {
+        fn states(&self) -> Vec<i32> {
+            (0..3).collect()
+        }
+
+        fn actions(&self) -> Vec<i32> {
+            vec![-1, 1]
+        }
+
+        fn actions_at(&self, _state: &i32) -> Vec<i32> {
+            self.actions()
+        }
+
+        fn reward(&self, _state: &i32, _action: &i32) -> f64 {
+            -1.0
+        }
+
+        fn transition(&self, state: &i32, action: &i32) -> Option<i32> {
+            let next = (state + action).max(0);
+            (next < 3).then_some(next)
+        }
+    }
+
+    fn values() -> HashMap<i32, f64> {
+        HashMap::from([(0, 0.0), (1, 1.0), (2, 0.0)])
+    }
+
+    #[test]
+    fn greedy_ties_share_the_mass() {
+        let policy = GreedyValuePolicy::new(&Line, values());
+        assert_eq!(policy.action_probs(&1), vec![(-1, 0.5), (1, 0.5)]);
+        assert_eq!(policy.action_probs(&0), vec![(-1, 0.0), (1, 1.0)]);
+        assert_eq!(policy.prob(&0, &-1), 0.0);
+        assert_eq!(policy.log_prob(&0, &-1), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn epsilon_greedy_probs_match_samples() {
+        let mut policy = EpsilonGreedyValuePolicy::new(&Line, values(), 0.2);
+        assert_eq!(policy.action_probs(&2), vec![(-1, 0.9), (1, 0.1)]);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 100000;
+        let lefts = (0..n)
+            .filter(|_| policy.gen_action(&2, &mut rng) == Some(-1))
+            .count();
+        assert!((lefts as f64 / n as f64 - 0.9).abs() < 0.01);
+
+        policy.turn_off_random();
+        assert_eq!(policy.action_probs(&2), vec![(-1, 1.0), (1, 0.0)]);
+    }
+
+    #[test]
+    fn uniform_and_tabular_probs() {
+        let uniform = UniformRandomPolicy::new(&Line);
+        assert_eq!(uniform.action_probs(&0), vec![(-1, 0.5), (1, 0.5)]);
+
+        let tabular = TabularPolicy::new(HashMap::from([(0, 1)]));
+        assert_eq!(tabular.action_probs(&0), vec![(1, 1.0)]);
+        assert_eq!(tabular.prob(&0, &-1), 0.0);
+        assert!(tabular.action_probs(&1).is_empty());
+    }
 }
@@ -1,4 +1,5 @@
 use super::policy::Policy;
+use peroxide::fuga::*;
 
 pub trait MarkovDecisionProcess<S, A> {
     fn states(&self) -> Vec<S>;
@@ -11,6 +12,50 @@ pub trait MarkovDecisionProcess<S, A> {
         let next_state = self.transition(state, action);
         (next_state, reward)
     }
+
+    /// Next-state distribution of taking `action` in `state`
+    ///
+    /// Each outcome is `(next_state, probability, reward)` where `None` means the episode terminates.
+    /// The default describes a deterministic process built from `transition` and `reward`.
+    /// Stochastic processes override this and keep `transition`/`reward` as the nominal outcome.
+    fn transition_probs(&self, state: &S, action: &A) -> Vec<(Option<S>, f64, f64)> {
+        vec![(
+            self.transition(state, action),
+            1.0,
+            self.reward(state, action),
+        )]
+    }
+
+    /// Sample one step from `transition_probs`
+    fn sample_step(&self, state: &S, action: &A, rng: &mut dyn RngCore) -> (Option<S>, f64) {
+        let mut outcomes = self.transition_probs(state, action);
+        let u = rng.gen::<f64>();
+        let mut acc = 0f64;
+        let last = outcomes.pop().expect("Empty transition distribution");
+        for (next_state, p, r) in outcomes {
+            acc += p;
+            if u < acc {
+                return (next_state, r);
+            }
+        }
+        (last.0, last.2)
+    }
+}
+
+/// Process with a well-defined start of an episode
+pub trait EpisodicProcess<S, A>: MarkovDecisionProcess<S, A> {
+    fn init_state(&self, rng: &mut dyn RngCore) -> S;
+
+    /// Maximum number of steps per episode; reaching it truncates the episode (not terminal)
+    fn time_limit(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Episodic process that can start an episode from any `(state, action)` pair
+pub trait ExploringStarts<S, A>: EpisodicProcess<S, A> {
+    /// Sample a start pair; every non-terminal `(s, a)` must have positive probability
+    fn exploring_start(&self, rng: &mut dyn RngCore) -> (S, A);
 }
 
 pub trait MarkovRewardProcess<S, A>: MarkovDecisionProcess<S, A> {
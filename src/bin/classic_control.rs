@@ -0,0 +1,114 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{
+        features::{Coordinates, StateRange, TileCoding},
+        function::LinearActionValueFunction,
+        process::EpisodicProcess,
+    },
+    env::{acrobot::Acrobot, cart_pole::CartPole, mountain_car::MountainCar},
+    learning::{
+        function_approximation::SemiGradientNStepSARSA,
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+};
+
+fn main() {
+    let seed = 42;
+
+    // 1. Mountain Car (no time limit and optimistic zero init, as in Sutton & Barto Figure 10.1)
+    let mountain_car = MountainCar::new(ExMethod::RK4, 1, None);
+    let mountain_car_history = run(
+        "Mountain Car",
+        &Trainer::new(500, 10000),
+        &mountain_car,
+        mountain_car.state_range(),
+        1f64,
+        0f64,
+        seed,
+    );
+
+    // 2. Cart-Pole (zero init is pessimistic here, so explore with ε-greedy)
+    let cart_pole = CartPole::default();
+    let cart_pole_history = run(
+        "Cart-Pole",
+        &Trainer::new(500, usize::MAX),
+        &cart_pole,
+        cart_pole.state_range(),
+        0.99,
+        0.05,
+        seed,
+    );
+
+    // 3. Acrobot
+    let acrobot = Acrobot::default();
+    let acrobot_history = run(
+        "Acrobot",
+        &Trainer::new(200, usize::MAX),
+        &acrobot,
+        acrobot.state_range(),
+        1f64,
+        0f64,
+        seed,
+    );
+
+    // Store all episodes' length
+    let length = |h: &TrainingHistory| {
+        h.get_lengths()
+            .iter()
+            .map(|l| *l as u64)
+            .collect::<Vec<u64>>()
+    };
+    for (name, history) in [
+        ("mountain_car", &mountain_car_history),
+        ("cart_pole", &cart_pole_history),
+        ("acrobot", &acrobot_history),
+    ] {
+        let mut df = DataFrame::new(vec![]);
+        df.push("length", Series::new(length(history)));
+        df.write_parquet(
+            &format!("./data/{}-tile_coding_sarsa-length.parquet", name),
+            CompressionOptions::Uncompressed,
+        )
+        .expect("Can't write parquet file");
+    }
+}
+
+/// Semi-gradient SARSA on 8 tilings of 8 tiles per dimension (zero init)
+fn run<S, A, M>(
+    name: &str,
+    trainer: &Trainer,
+    env: &M,
+    range: StateRange,
+    gamma: f64,
+    epsilon: f64,
+    seed: u64,
+) -> TrainingHistory
+where
+    S: Clone + PartialEq + Coordinates,
+    A: Clone + PartialEq,
+    M: EpisodicProcess<S, A>,
+{
+    let num_tilings = 8;
+    let features = TileCoding::new(range, num_tilings, 8, 4096);
+    let mut agent = SemiGradientNStepSARSA::new(
+        env,
+        LinearActionValueFunction::new(features, env.actions(), 0f64),
+        Box::new(ConstantStepsize::new(0.5 / num_tilings as f64)),
+        gamma,
+        epsilon,
+        1,
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, &mut agent, &mut rng, &mut [&mut progress]);
+    let lengths = history.get_lengths();
+    let tail = &lengths[lengths.len().saturating_sub(20)..];
+    println!(
+        "{}: mean length of the last {} episodes = {:.1}",
+        name,
+        tail.len(),
+        tail.iter().sum::<usize>() as f64 / tail.len() as f64
+    );
+    history
+}
@@ -0,0 +1,92 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable},
+    env::grid_world::{write_episode_parquet, GridWorld, GridWorldAction},
+    learning::{
+        control::{QLearning, SARSA},
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+};
+
+type Episode = Vec<((usize, usize), GridWorldAction, f64)>;
+
+fn main() {
+    // Cliff Walking with ε = 0.1, α = 0.5 (Sutton & Barto Example 6.6)
+    let env = GridWorld::cliff_walking();
+    let gamma = 1f64;
+    let epsilon = 0.1;
+    let alpha = 0.5;
+    let trainer = Trainer::new(500, 10000);
+    let seed = 42;
+
+    // 1. SARSA: learns the safe path away from the cliff
+    let mut sarsa = SARSA::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        epsilon,
+    );
+    let (sarsa_history, sarsa_episode) = run("SARSA", &trainer, &env, &mut sarsa, seed);
+
+    // 2. Q-Learning: learns the optimal path along the edge, but falls more often while exploring
+    let mut q_learning = QLearning::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        epsilon,
+    );
+    let (q_learning_history, q_learning_episode) =
+        run("Q-Learning", &trainer, &env, &mut q_learning, seed);
+
+    // Store the return of every episode, as in Figure 6.4
+    let mut df = DataFrame::new(vec![]);
+    df.push("sarsa", Series::new(sarsa_history.get_returns().to_vec()));
+    df.push(
+        "q_learning",
+        Series::new(q_learning_history.get_returns().to_vec()),
+    );
+    df.write_parquet(
+        "./data/grid_world/cliff-td_control-return.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+    env.write_layout_parquet("./data/grid_world/cliff")
+        .expect("Can't write parquet file");
+
+    // Store greedy paths
+    write_episode_parquet(
+        &sarsa_episode,
+        "./data/grid_world/cliff-sarsa-episode.parquet",
+    )
+    .expect("Can't write parquet file");
+    write_episode_parquet(
+        &q_learning_episode,
+        "./data/grid_world/cliff-q_learning-episode.parquet",
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> (TrainingHistory, Episode) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let returns = history.get_returns();
+    let tail = &returns[returns.len() - 100..];
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{}: mean return of the last {} episodes = {:.1}, greedy length = {}, return = {}",
+        name,
+        tail.len(),
+        tail.iter().sum::<f64>() / tail.len() as f64,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    (history, test_episode)
+}
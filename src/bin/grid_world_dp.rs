@@ -0,0 +1,78 @@
+use peroxide::fuga::*;
+use rlai::env::grid_world::{GridCell, GridWorld, RewardSpec};
+use rlai::planning::dynamic_programming::{PolicyIteration, ValueIteration};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls.clone());
+
+    let gamma = 0.95;
+    let tol = 1e-8;
+    let max_sweeps = 1000;
+
+    // 1. Value Iteration
+    let vi = ValueIteration::new(gamma, tol, max_sweeps).solve(&env);
+    println!("Value Iteration: {} sweeps", vi.get_residuals().len());
+
+    // 2. Policy Iteration
+    let pi = PolicyIteration::new(gamma, tol, max_sweeps).solve(&env);
+    println!("Policy Iteration: {} sweeps", pi.get_residuals().len());
+
+    let max_diff = vi
+        .get_value_function()
+        .iter()
+        .map(|(s, v)| (v - pi.get_value_function()[s]).abs())
+        .fold(0f64, f64::max);
+    println!("max |V_VI - V_PI| = {:.4e}", max_diff);
+
+    // Store optimal value function
+    let mut states = vi.get_value_function().keys().cloned().collect::<Vec<_>>();
+    states.sort();
+    let mut df = DataFrame::new(vec![]);
+    df.push(
+        "x",
+        Series::new(states.iter().map(|s| s.0 as u64).collect::<Vec<u64>>()),
+    );
+    df.push(
+        "y",
+        Series::new(states.iter().map(|s| s.1 as u64).collect::<Vec<u64>>()),
+    );
+    df.push(
+        "value",
+        Series::new(
+            states
+                .iter()
+                .map(|s| vi.get_value_function()[s])
+                .collect::<Vec<f64>>(),
+        ),
+    );
+    df.print();
+    df.write_parquet(
+        "./data/grid_world/dp-value_iteration-value.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+
+    // 3. Shortest path: -1 per step, a near goal worth 0 and a far goal worth 10
+    let mut shortest = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+    let mut rewards = RewardSpec::new(-1.0, -1.0, -1.0, 0.0);
+    rewards.set_cell_reward((4, 0), 10.0);
+    shortest.set_cell(&(4, 0), GridCell::Goal);
+    shortest.set_reward_spec(rewards);
+    let sp = ValueIteration::new(1.0, tol, max_sweeps).solve(&shortest);
+    println!(
+        "Shortest path: V(start) = {:.1} ({} sweeps)",
+        sp.get_value_function()[&shortest.get_init_state()],
+        sp.get_residuals().len()
+    );
+
+    // Store residuals
+    let mut df = DataFrame::new(vec![]);
+    df.push("residual", Series::new(vi.get_residuals().to_vec()));
+    df.write_parquet(
+        "./data/grid_world/dp-value_iteration-residual.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
@@ -0,0 +1,108 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable},
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        dyna::DynaQ,
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+    planning::{dynamic_programming::ValueIteration, model::TabularModel},
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let epsilon = 0.1;
+    let alpha = 0.1;
+    let trainer = Trainer::new(300, 1000);
+    let seed = 42;
+
+    let dyna_q = |n_planning: usize, kappa: f64| {
+        DynaQ::new(
+            QTable::from_mdp(&env, 0f64),
+            TabularModel::deterministic(),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+            epsilon,
+            n_planning,
+            kappa,
+        )
+    };
+
+    // 1. Dyna-Q with 0 (= Q-Learning), 5 and 50 planning steps
+    let mut histories = vec![];
+    for n in [0, 5, 50] {
+        let mut agent = dyna_q(n, 0f64);
+        let name = format!("Dyna-Q (n = {})", n);
+        histories.push((
+            format!("dyna_q_{}", n),
+            run(&name, &trainer, &env, &mut agent, seed),
+        ));
+    }
+
+    // 2. Dyna-Q+ with 50 planning steps
+    let mut dyna_q_plus = dyna_q(50, 1e-4);
+    histories.push((
+        "dyna_q_plus_50".to_string(),
+        run("Dyna-Q+ (n = 50)", &trainer, &env, &mut dyna_q_plus, seed),
+    ));
+
+    // 3. Value iteration on the learned model
+    let model = dyna_q_plus.get_model();
+    let vi_model = ValueIteration::new(gamma, 1e-10, 1000).solve(model);
+    let vi_env = ValueIteration::new(gamma, 1e-10, 1000).solve(&env);
+    let max_diff = vi_model
+        .get_value_function()
+        .iter()
+        .map(|(s, v)| (v - vi_env.get_value_function()[s]).abs())
+        .fold(0f64, f64::max);
+    println!(
+        "Learned model: {} pairs, max |V_model - V*| = {:.4e}",
+        model.observed_pairs().len(),
+        max_diff
+    );
+
+    // Store all episodes' length
+    let mut df = DataFrame::new(vec![]);
+    for (name, history) in histories.iter() {
+        df.push(
+            name,
+            Series::new(
+                history
+                    .get_lengths()
+                    .iter()
+                    .map(|l| *l as u64)
+                    .collect::<Vec<u64>>(),
+            ),
+        );
+    }
+    df.write_parquet(
+        "./data/grid_world/dyna-epsilon_greedy-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> TrainingHistory {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{} test: length = {}, return = {}",
+        name,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
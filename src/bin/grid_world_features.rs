@@ -0,0 +1,111 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{
+        features::{FourierBasis, PolynomialBasis, RadialBasis, TileCoding},
+        function::{FeatureExtractor, LinearActionValueFunction},
+        process::MarkovDecisionProcess,
+    },
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        function_approximation::SemiGradientNStepSARSA,
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+    let range = env.coordinate_range();
+
+    let trainer = Trainer::new(500, 1000);
+    let seed = 42;
+    let num_tilings = 8;
+
+    // Tile coding steps are divided by the number of active features
+    let tile_coding = TileCoding::new(range.clone(), num_tilings, 4, 1024);
+    let tile_history = run(
+        "Tile coding",
+        &trainer,
+        &env,
+        tile_coding,
+        0.5 / num_tilings as f64,
+        seed,
+    );
+    let rbf_history = run(
+        "RBF",
+        &trainer,
+        &env,
+        RadialBasis::grid(range.clone(), 5, 0.125),
+        0.2,
+        seed,
+    );
+    let fourier_history = run(
+        "Fourier",
+        &trainer,
+        &env,
+        FourierBasis::new(range.clone(), 4),
+        0.05,
+        seed,
+    );
+    let polynomial_history = run(
+        "Polynomial",
+        &trainer,
+        &env,
+        PolynomialBasis::new(range, 3),
+        0.05,
+        seed,
+    );
+
+    // Store all episodes' length
+    let length = |h: &TrainingHistory| {
+        h.get_lengths()
+            .iter()
+            .map(|l| *l as u64)
+            .collect::<Vec<u64>>()
+    };
+    let mut df = DataFrame::new(vec![]);
+    df.push("tile_coding", Series::new(length(&tile_history)));
+    df.push("rbf", Series::new(length(&rbf_history)));
+    df.push("fourier", Series::new(length(&fourier_history)));
+    df.push("polynomial", Series::new(length(&polynomial_history)));
+    df.write_parquet(
+        "./data/grid_world/features-n_step_sarsa-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+/// Semi-gradient 4-step SARSA on top of `features`
+fn run<F: FeatureExtractor<(usize, usize)>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    features: F,
+    alpha: f64,
+    seed: u64,
+) -> TrainingHistory {
+    let num_features = features.num_features();
+    let mut agent = SemiGradientNStepSARSA::new(
+        env,
+        LinearActionValueFunction::new(features, env.actions(), 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        0.95,
+        0.1,
+        4,
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, &mut agent, &mut rng, &mut [&mut progress]);
+    let test_episode: Vec<((usize, usize), GridWorldAction, f64)> =
+        trainer.evaluate(env, &mut agent, &mut rng);
+    println!(
+        "{} ({} features) test: length = {}, return = {}",
+        name,
+        num_features,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
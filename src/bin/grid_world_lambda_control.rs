@@ -0,0 +1,123 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable},
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        control::{SARSALambda, TrueOnlineSARSALambda, WatkinsQLambda},
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+        value_prediction::EligibilityTrace,
+    },
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let lambda = 0.9;
+    let epsilon = 0.1;
+    let alpha = 0.1;
+    let trainer = Trainer::new(500, 1000);
+    let seed = 42;
+
+    // 1. SARSA(λ) with accumulating traces
+    let mut sarsa_acc = SARSALambda::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        lambda,
+        epsilon,
+        EligibilityTrace::Accumulating,
+    );
+    let sarsa_acc_history = run(
+        "SARSA(λ) accumulating",
+        &trainer,
+        &env,
+        &mut sarsa_acc,
+        seed,
+    );
+
+    // 2. SARSA(λ) with replacing traces
+    let mut sarsa_rep = SARSALambda::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        lambda,
+        epsilon,
+        EligibilityTrace::Replacing,
+    );
+    let sarsa_rep_history = run("SARSA(λ) replacing", &trainer, &env, &mut sarsa_rep, seed);
+
+    // 3. Watkins's Q(λ)
+    let mut watkins = WatkinsQLambda::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        lambda,
+        epsilon,
+        EligibilityTrace::Replacing,
+    );
+    let watkins_history = run("Watkins's Q(λ)", &trainer, &env, &mut watkins, seed);
+
+    // 4. True online SARSA(λ)
+    let mut true_online = TrueOnlineSARSALambda::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        lambda,
+        epsilon,
+    );
+    let true_online_history = run(
+        "True online SARSA(λ)",
+        &trainer,
+        &env,
+        &mut true_online,
+        seed,
+    );
+
+    // Store all episodes' length
+    let length = |h: &TrainingHistory| {
+        h.get_lengths()
+            .iter()
+            .map(|l| *l as u64)
+            .collect::<Vec<u64>>()
+    };
+    let mut df = DataFrame::new(vec![]);
+    df.push(
+        "sarsa_accumulating",
+        Series::new(length(&sarsa_acc_history)),
+    );
+    df.push("sarsa_replacing", Series::new(length(&sarsa_rep_history)));
+    df.push("watkins_q", Series::new(length(&watkins_history)));
+    df.push(
+        "true_online_sarsa",
+        Series::new(length(&true_online_history)),
+    );
+    df.write_parquet(
+        "./data/grid_world/lambda_control-epsilon_greedy-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> TrainingHistory {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{} test: length = {}, return = {}",
+        name,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
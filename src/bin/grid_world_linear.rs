@@ -0,0 +1,125 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{
+        agent::Agent,
+        function::{
+            LinearActionValueFunction, LinearValueFunction, TabularFeatures, ValueFunction,
+        },
+        policy::UniformRandomPolicy,
+        process::MarkovDecisionProcess,
+    },
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        function_approximation::{
+            ApproxPredictionAgent, ApproxValuePredictor, GradientMC, SemiGradientNStepSARSA,
+            SemiGradientTD0,
+        },
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let epsilon = 0.1;
+    let alpha = 0.05;
+    let trainer = Trainer::new(2000, 1000);
+    let seed = 42;
+
+    // 1. Prediction of the uniform random policy: gradient MC vs semi-gradient TD(0)
+    let mut gradient_mc = ApproxPredictionAgent::new(
+        UniformRandomPolicy::new(&env),
+        GradientMC::new(
+            LinearValueFunction::new(TabularFeatures::from_mdp(&env), 0f64),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+        ),
+    );
+    run("Gradient MC", &trainer, &env, &mut gradient_mc, seed);
+
+    let mut semi_gradient_td0 = ApproxPredictionAgent::new(
+        UniformRandomPolicy::new(&env),
+        SemiGradientTD0::new(
+            LinearValueFunction::new(TabularFeatures::from_mdp(&env), 0f64),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+        ),
+    );
+    run(
+        "Semi-gradient TD(0)",
+        &trainer,
+        &env,
+        &mut semi_gradient_td0,
+        seed,
+    );
+
+    let v_mc = gradient_mc.get_predictor().get_value_function();
+    let v_td = semi_gradient_td0.get_predictor().get_value_function();
+    let states = env.states();
+    let rms = (states
+        .iter()
+        .map(|s| (v_mc.value(s) - v_td.value(s)).powi(2))
+        .sum::<f64>()
+        / states.len() as f64)
+        .sqrt();
+    println!("RMS(v_MC - v_TD) = {:.4}", rms);
+
+    // 2. Control: semi-gradient n-step SARSA
+    let trainer = Trainer::new(500, 1000);
+    let mut histories = vec![];
+    for n in [1, 4] {
+        let mut sarsa = SemiGradientNStepSARSA::new(
+            &env,
+            LinearActionValueFunction::new(TabularFeatures::from_mdp(&env), env.actions(), 0f64),
+            Box::new(ConstantStepsize::new(0.5)),
+            gamma,
+            epsilon,
+            n,
+        );
+        let name = format!("Semi-gradient {}-step SARSA", n);
+        histories.push((n, run(&name, &trainer, &env, &mut sarsa, seed)));
+    }
+
+    // Store all episodes' length
+    let mut df = DataFrame::new(vec![]);
+    for (n, h) in histories.iter() {
+        df.push(
+            &format!("n_{}", n),
+            Series::new(
+                h.get_lengths()
+                    .iter()
+                    .map(|l| *l as u64)
+                    .collect::<Vec<u64>>(),
+            ),
+        );
+    }
+    df.write_parquet(
+        "./data/grid_world/semi_gradient_n_step_sarsa-epsilon_greedy-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> TrainingHistory {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{} test: length = {}, return = {}",
+        name,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
@@ -0,0 +1,54 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{function::QTable, process::MarkovDecisionProcess},
+    env::grid_world::{write_episode_parquet, GridWorld},
+    learning::{
+        control::{ActionValuePredictor, MonteCarloES},
+        trainer::{ProgressBarCallback, Trainer},
+    },
+    planning::dynamic_programming::ValueIteration,
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let mut agent = MonteCarloES::new(QTable::from_mdp(&env, 0f64), gamma);
+
+    // Train
+    let mut rng = StdRng::seed_from_u64(42);
+    let trainer = Trainer::new(20000, 1000);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train_exploring_starts(&env, &mut agent, &mut rng, &mut [&mut progress]);
+
+    // Test
+    let test_episode = trainer.evaluate(&env, &mut agent, &mut rng);
+    println!(
+        "MC-ES test: length = {}, return = {}",
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+
+    // Compare max_a Q(s, a) with the optimal value function
+    let vi = ValueIteration::new(gamma, 1e-10, 1000).solve(&env);
+    let max_diff = env
+        .states()
+        .iter()
+        .map(|s| (agent.get_q_table().max_value(s) - vi.get_value_function()[s]).abs())
+        .fold(0f64, f64::max);
+    println!("max |max_a Q - V*| = {:.4e}", max_diff);
+
+    // Store test episode & all episodes' length
+    let prefix = "./data/grid_world/mc_es-greedy";
+    write_episode_parquet(&test_episode, &format!("{}-test.parquet", prefix))
+        .expect("Can't write parquet file");
+    history
+        .write_parquet(&format!("{}-length.parquet", prefix))
+        .expect("Can't write parquet file");
+
+    // Store Goal & Terminal States
+    env.write_layout_parquet(prefix)
+        .expect("Can't write parquet file");
+}
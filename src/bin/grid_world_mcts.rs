@@ -0,0 +1,116 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::policy::UniformRandomPolicy,
+    env::grid_world::{write_episode_parquet, GridWorld},
+    learning::trainer::Trainer,
+    planning::mcts::{MCTSAgent, MCTS},
+};
+use std::collections::VecDeque;
+
+fn main() {
+    let gamma = 0.95;
+    let exploration = 1f64;
+    let num_simulations = 2000;
+    let max_depth = 50;
+    let trainer = Trainer::new(1, 200);
+    let mut rng = StdRng::seed_from_u64(42);
+
+    // 1. Hand-coded 5x5 layout
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+    let planner = MCTS::new(
+        UniformRandomPolicy::new(&env),
+        exploration,
+        num_simulations,
+        max_depth,
+        gamma,
+    );
+    let mut agent = MCTSAgent::new(&env, planner);
+    let episode = trainer.evaluate(&env, &mut agent, &mut rng);
+    report("5x5", &episode);
+    if let Some(result) = agent.get_last_result() {
+        for (a, n, q) in result.get_action_stats() {
+            println!("  last root: {:?} visits = {}, Q = {:.4}", a, n, q);
+        }
+    }
+    env.write_layout_parquet("./data/grid_world/mcts-5x5")
+        .expect("Can't write parquet file");
+    write_episode_parquet(&episode, "./data/grid_world/mcts-5x5-episode.parquet")
+        .expect("Can't write parquet file");
+
+    // 2. Procedurally generated 8x8 maze
+    let maze = random_maze(8, 8, 0.2, &mut rng);
+    let planner = MCTS::new(
+        UniformRandomPolicy::new(&maze),
+        exploration,
+        num_simulations,
+        2 * max_depth,
+        gamma,
+    );
+    let mut agent = MCTSAgent::new(&maze, planner);
+    let episode = trainer.evaluate(&maze, &mut agent, &mut rng);
+    report("8x8 maze", &episode);
+    maze.write_layout_parquet("./data/grid_world/mcts-maze")
+        .expect("Can't write parquet file");
+    maze.to_map()
+        .write_file("./data/grid_world/mcts-maze.txt")
+        .expect("Can't write map file");
+    write_episode_parquet(&episode, "./data/grid_world/mcts-maze-episode.parquet")
+        .expect("Can't write parquet file");
+}
+
+fn report<A>(name: &str, episode: &[((usize, usize), A, f64)]) {
+    println!(
+        "MCTS on {}: length = {}, return = {}",
+        name,
+        episode.len(),
+        episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+}
+
+/// Corner-to-corner maze with each other cell blocked with probability `density`, resampled until solvable
+fn random_maze(num_x: usize, num_y: usize, density: f64, rng: &mut dyn RngCore) -> GridWorld {
+    let start = (0, 0);
+    let goal = (num_x - 1, num_y - 1);
+    loop {
+        let blocked = (0..num_x)
+            .flat_map(|x| (0..num_y).map(move |y| (x, y)))
+            .filter(|s| *s != start && *s != goal && rng.gen::<f64>() < density)
+            .collect::<Vec<_>>();
+        if is_reachable(num_x, num_y, start, goal, &blocked) {
+            return GridWorld::with_walls(num_x, num_y, start, goal, blocked);
+        }
+    }
+}
+
+/// Breadth-first search over free cells
+fn is_reachable(
+    num_x: usize,
+    num_y: usize,
+    start: (usize, usize),
+    goal: (usize, usize),
+    blocked: &[(usize, usize)],
+) -> bool {
+    let mut visited = vec![vec![false; num_y]; num_x];
+    let mut queue = VecDeque::from(vec![start]);
+    visited[start.0][start.1] = true;
+    while let Some((x, y)) = queue.pop_front() {
+        if (x, y) == goal {
+            return true;
+        }
+        let neighbors = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbors {
+            if nx < num_x && ny < num_y && !visited[nx][ny] && !blocked.contains(&(nx, ny)) {
+                visited[nx][ny] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    false
+}
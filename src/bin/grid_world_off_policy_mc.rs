@@ -0,0 +1,74 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable, policy::UniformRandomPolicy},
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        control::OffPolicyMCControl,
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ImportanceSampling,
+    },
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let trainer = Trainer::new(200000, 1000);
+    let seed = 42;
+
+    // 1. Ordinary importance sampling
+    let mut ordinary = OffPolicyMCControl::new(
+        QTable::from_mdp(&env, 0f64),
+        UniformRandomPolicy::new(&env),
+        gamma,
+        ImportanceSampling::Ordinary,
+    );
+    let ordinary_history = run("Ordinary IS", &trainer, &env, &mut ordinary, seed);
+
+    // 2. Weighted importance sampling
+    let mut weighted = OffPolicyMCControl::new(
+        QTable::from_mdp(&env, 0f64),
+        UniformRandomPolicy::new(&env),
+        gamma,
+        ImportanceSampling::Weighted,
+    );
+    let weighted_history = run("Weighted IS", &trainer, &env, &mut weighted, seed);
+
+    // Store all episodes' return
+    let mut df = DataFrame::new(vec![]);
+    df.push(
+        "ordinary",
+        Series::new(ordinary_history.get_returns().to_vec()),
+    );
+    df.push(
+        "weighted",
+        Series::new(weighted_history.get_returns().to_vec()),
+    );
+    df.write_parquet(
+        "./data/grid_world/off_policy_mc-uniform-return.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> TrainingHistory {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{} test: length = {}, return = {}",
+        name,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
@@ -0,0 +1,106 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable, process::MarkovDecisionProcess},
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        dyna::{DynaQ, PrioritizedSweepingAgent},
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+    planning::{
+        dynamic_programming::ValueIteration, model::TabularModel,
+        prioritized_sweeping::PrioritizedSweeping,
+    },
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let epsilon = 0.1;
+    let alpha = 0.1;
+    let theta = 1e-6;
+    let n_planning = 5;
+
+    // 1. Planning on the given model: prioritized sweeping vs value iteration
+    let vi = ValueIteration::new(gamma, theta, 1000).solve(&env);
+    let num_pairs: usize = env.states().iter().map(|s| env.actions_at(s).len()).sum();
+    let (q_table, ps_updates) =
+        PrioritizedSweeping::new(gamma, 1f64, theta, n_planning).solve(&env, usize::MAX);
+    let max_diff = env
+        .states()
+        .iter()
+        .map(|s| (q_table.max_value(s) - vi.get_value_function()[s]).abs())
+        .fold(0f64, f64::max);
+    println!(
+        "Value iteration: {} backups, prioritized sweeping: {} backups",
+        vi.get_residuals().len() * num_pairs,
+        ps_updates
+    );
+    println!("max |max_a Q_PS - V_VI| = {:.4e}", max_diff);
+
+    // 2. Learning: prioritized sweeping vs Dyna-Q with the same planning budget
+    let trainer = Trainer::new(300, 1000);
+    let seed = 42;
+
+    let mut ps_agent = PrioritizedSweepingAgent::new(
+        QTable::from_mdp(&env, 0f64),
+        TabularModel::deterministic(),
+        PrioritizedSweeping::new(gamma, alpha, theta, n_planning),
+        epsilon,
+    );
+    let ps_history = run("Prioritized sweeping", &trainer, &env, &mut ps_agent, seed);
+    println!(
+        "Prioritized sweeping: {} planning backups",
+        ps_agent.get_num_updates()
+    );
+
+    let mut dyna_q = DynaQ::new(
+        QTable::from_mdp(&env, 0f64),
+        TabularModel::deterministic(),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        epsilon,
+        n_planning,
+        0f64,
+    );
+    let dyna_q_history = run("Dyna-Q", &trainer, &env, &mut dyna_q, seed);
+
+    // Store all episodes' length
+    let length = |h: &TrainingHistory| {
+        h.get_lengths()
+            .iter()
+            .map(|l| *l as u64)
+            .collect::<Vec<u64>>()
+    };
+    let mut df = DataFrame::new(vec![]);
+    df.push("prioritized_sweeping", Series::new(length(&ps_history)));
+    df.push("dyna_q", Series::new(length(&dyna_q_history)));
+    df.write_parquet(
+        "./data/grid_world/prioritized_sweeping-epsilon_greedy-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> TrainingHistory {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{} test: length = {}, return = {}",
+        name,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
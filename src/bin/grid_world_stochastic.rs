@@ -0,0 +1,76 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::function::QTable,
+    env::grid_world::{GridWorld, WindMode},
+    learning::{
+        control::SARSA,
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+    planning::dynamic_programming::ValueIteration,
+};
+
+fn main() {
+    // 1. FrozenLake: Value Iteration on the exact slippery dynamics
+    let frozen_lake = GridWorld::frozen_lake();
+    let vi = ValueIteration::new(0.99, 1e-8, 1000).solve(&frozen_lake);
+    println!(
+        "FrozenLake: V(start) = {:.4} ({} sweeps)",
+        vi.get_value_function()[&frozen_lake.get_init_state()],
+        vi.get_residuals().len()
+    );
+
+    // 2. Windy Gridworld: SARSA with ε = 0.1, α = 0.5 (Sutton & Barto Example 6.5)
+    let trainer = Trainer::new(170, 10000);
+    let seed = 42;
+    let steady_history = run(
+        "Windy Gridworld",
+        &trainer,
+        &GridWorld::windy(WindMode::Steady),
+        seed,
+    );
+    let stochastic_history = run(
+        "Stochastic Windy Gridworld",
+        &trainer,
+        &GridWorld::windy(WindMode::Stochastic),
+        seed,
+    );
+
+    // Store all episodes' length
+    let length = |h: &TrainingHistory| {
+        h.get_lengths()
+            .iter()
+            .map(|l| *l as u64)
+            .collect::<Vec<u64>>()
+    };
+    let mut df = DataFrame::new(vec![]);
+    df.push("steady", Series::new(length(&steady_history)));
+    df.push("stochastic", Series::new(length(&stochastic_history)));
+    df.write_parquet(
+        "./data/grid_world/windy-sarsa-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run(name: &str, trainer: &Trainer, env: &GridWorld, seed: u64) -> TrainingHistory {
+    let mut agent = SARSA::new(
+        QTable::from_mdp(env, 0f64),
+        Box::new(ConstantStepsize::new(0.5)),
+        1f64,
+        0.1,
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, &mut agent, &mut rng, &mut [&mut progress]);
+    let lengths = history.get_lengths();
+    let tail = &lengths[lengths.len() - 20..];
+    println!(
+        "{}: {} steps in total, mean length of the last {} episodes = {:.1}",
+        name,
+        lengths.iter().sum::<usize>(),
+        tail.len(),
+        tail.iter().sum::<usize>() as f64 / tail.len() as f64
+    );
+    history
+}
@@ -0,0 +1,95 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable},
+    env::grid_world::{GridWorld, GridWorldAction},
+    learning::{
+        control::{ActionValuePredictor, ExpectedSARSA, QLearning, SARSA},
+        trainer::{ProgressBarCallback, Trainer, TrainingHistory},
+        util::ConstantStepsize,
+    },
+};
+
+fn main() {
+    let goal_state = (4, 3);
+    let walls = vec![(1, 0), (1, 1), (1, 2), (1, 3), (3, 4), (3, 3)];
+    let env = GridWorld::with_walls(5, 5, (0, 0), goal_state, walls);
+
+    let gamma = 0.95;
+    let epsilon = 0.1;
+    let alpha = 0.5;
+    let trainer = Trainer::new(500, 1000);
+    let seed = 42;
+
+    // 1. SARSA
+    let mut sarsa = SARSA::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        epsilon,
+    );
+    let sarsa_history = run("SARSA", &trainer, &env, &mut sarsa, seed);
+
+    // 2. Q-Learning
+    let mut q_learning = QLearning::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        epsilon,
+    );
+    let q_learning_history = run("Q-Learning", &trainer, &env, &mut q_learning, seed);
+    println!(
+        "Q-Learning Q(init): {:?}",
+        q_learning
+            .get_q_table()
+            .action_values(&env.get_init_state())
+    );
+
+    // 3. Expected SARSA
+    let mut expected_sarsa = ExpectedSARSA::new(
+        QTable::from_mdp(&env, 0f64),
+        Box::new(ConstantStepsize::new(alpha)),
+        gamma,
+        epsilon,
+    );
+    let expected_sarsa_history = run("Expected SARSA", &trainer, &env, &mut expected_sarsa, seed);
+
+    // Store all episodes' length
+    let length = |h: &TrainingHistory| {
+        h.get_lengths()
+            .iter()
+            .map(|l| *l as u64)
+            .collect::<Vec<u64>>()
+    };
+    let mut df = DataFrame::new(vec![]);
+    df.push("sarsa", Series::new(length(&sarsa_history)));
+    df.push("q_learning", Series::new(length(&q_learning_history)));
+    df.push(
+        "expected_sarsa",
+        Series::new(length(&expected_sarsa_history)),
+    );
+    df.write_parquet(
+        "./data/grid_world/td_control-epsilon_greedy-length.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+fn run<G: Agent<(usize, usize), GridWorldAction>>(
+    name: &str,
+    trainer: &Trainer,
+    env: &GridWorld,
+    agent: &mut G,
+    seed: u64,
+) -> TrainingHistory {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut progress = ProgressBarCallback::new();
+    let history = trainer.train(env, agent, &mut rng, &mut [&mut progress]);
+    let test_episode = trainer.evaluate(env, agent, &mut rng);
+    println!(
+        "{} test: length = {}, return = {}",
+        name,
+        test_episode.len(),
+        test_episode.iter().map(|(_, _, r)| r).sum::<f64>()
+    );
+    history
+}
@@ -0,0 +1,125 @@
+use peroxide::fuga::*;
+use rlai::{
+    base::{agent::Agent, function::QTable},
+    env::maximization_bias::{MaximizationBias, MaximizationBiasAction, MaximizationBiasState},
+    learning::{
+        control::{DoubleExpectedSARSA, DoubleQLearning, ExpectedSARSA, QLearning},
+        trainer::{Callback, Trainer},
+        util::ConstantStepsize,
+    },
+};
+
+type S = MaximizationBiasState;
+type A = MaximizationBiasAction;
+
+/// Records whether `Left` was taken in A, per episode
+struct LeftActionRecorder {
+    left: Vec<f64>,
+}
+
+impl Callback<S, A> for LeftActionRecorder {
+    fn on_train_begin(&mut self, num_episodes: usize) {
+        self.left = Vec::with_capacity(num_episodes);
+    }
+
+    fn on_episode_end(&mut self, _episode: usize, trajectory: &[(S, A, f64)]) {
+        let (num_a, num_left) = trajectory
+            .iter()
+            .filter(|(s, _, _)| *s == S::A)
+            .fold((0, 0), |(n, l), (_, a, _)| {
+                (n + 1, l + (*a == A::Left) as usize)
+            });
+        self.left.push(if num_a == 0 {
+            0f64
+        } else {
+            num_left as f64 / num_a as f64
+        });
+    }
+}
+
+fn main() {
+    let env = MaximizationBias::default();
+
+    let gamma = 1.0;
+    let epsilon = 0.1;
+    let alpha = 0.1;
+    let num_runs = 1000;
+    let trainer = Trainer::new(300, 100);
+
+    // Fraction of `Left` actions from A per episode, averaged over runs
+    let q_learning = run(&trainer, &env, num_runs, || {
+        QLearning::new(
+            QTable::from_mdp(&env, 0f64),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+            epsilon,
+        )
+    });
+    let double_q_learning = run(&trainer, &env, num_runs, || {
+        DoubleQLearning::new(
+            QTable::from_mdp(&env, 0f64),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+            epsilon,
+        )
+    });
+    let expected_sarsa = run(&trainer, &env, num_runs, || {
+        ExpectedSARSA::new(
+            QTable::from_mdp(&env, 0f64),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+            epsilon,
+        )
+    });
+    let double_expected_sarsa = run(&trainer, &env, num_runs, || {
+        DoubleExpectedSARSA::new(
+            QTable::from_mdp(&env, 0f64),
+            Box::new(ConstantStepsize::new(alpha)),
+            gamma,
+            epsilon,
+        )
+    });
+
+    let last = |v: &[f64]| v.last().cloned().unwrap_or(0.0);
+    println!("Left fraction at the last episode:");
+    println!("  Q-Learning:            {:.4}", last(&q_learning));
+    println!("  Double Q-Learning:     {:.4}", last(&double_q_learning));
+    println!("  Expected SARSA:        {:.4}", last(&expected_sarsa));
+    println!(
+        "  Double Expected SARSA: {:.4}",
+        last(&double_expected_sarsa)
+    );
+
+    // Store left fractions
+    let mut df = DataFrame::new(vec![]);
+    df.push("q_learning", Series::new(q_learning));
+    df.push("double_q_learning", Series::new(double_q_learning));
+    df.push("expected_sarsa", Series::new(expected_sarsa));
+    df.push("double_expected_sarsa", Series::new(double_expected_sarsa));
+    std::fs::create_dir_all("./data/maximization_bias").expect("Can't create data directory");
+    df.write_parquet(
+        "./data/maximization_bias/left_fraction.parquet",
+        CompressionOptions::Uncompressed,
+    )
+    .expect("Can't write parquet file");
+}
+
+/// Mean per-episode left fraction over `num_runs` independent runs (seeded by run index)
+fn run<G: Agent<S, A>, F: Fn() -> G>(
+    trainer: &Trainer,
+    env: &MaximizationBias,
+    num_runs: usize,
+    new_agent: F,
+) -> Vec<f64> {
+    let mut left = vec![0f64; trainer.get_num_episodes()];
+    for seed in 0..num_runs {
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let mut agent = new_agent();
+        let mut recorder = LeftActionRecorder { left: vec![] };
+        trainer.train(env, &mut agent, &mut rng, &mut [&mut recorder]);
+        left.iter_mut()
+            .zip(recorder.left)
+            .for_each(|(l, x)| *l += x / num_runs as f64);
+    }
+    left
+}
@@ -0,0 +1,199 @@
+use super::dynamics::{integrate, ControlInput};
+use crate::base::features::StateRange;
+use crate::base::process::{EpisodicProcess, MarkovDecisionProcess};
+use peroxide::fuga::*;
+use std::f64::consts::PI;
+use AcrobotAction as AA;
+
+const LINK_MASS_1: f64 = 1.0;
+const LINK_MASS_2: f64 = 1.0;
+const LINK_LENGTH_1: f64 = 1.0;
+/// Distance from each joint to the center of mass of its link
+const LINK_COM: f64 = 0.5;
+const LINK_MOI: f64 = 1.0;
+const GRAVITY: f64 = 9.8;
+const DT: f64 = 0.2;
+const MAX_VEL_1: f64 = 4.0 * PI;
+const MAX_VEL_2: f64 = 9.0 * PI;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Acrobot
+// └──────────────────────────────────────────────────────────┘
+/// Acrobot swing-up of Sutton & Barto (1998, Section 11.3)
+///
+/// State `[θ1, θ2, θ̇1, θ̇2]` with angles wrapped into [-π, π). A torque of -1, 0 or +1 on the
+/// second joint is held for 0.2 s while the equations of motion are integrated with `method`;
+/// the angular velocities are then clipped to ±4π and ±9π. Every step costs -1 and the episode
+/// terminates once the tip rises one link length above the base, -cos θ1 - cos(θ1 + θ2) > 1.
+/// Episodes start with every component uniform in [-0.1, 0.1].
+#[derive(Debug, Clone)]
+pub struct Acrobot {
+    method: ExMethod,
+    substeps: usize,
+    time_limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AcrobotAction {
+    Negative,
+    Zero,
+    Positive,
+}
+
+impl AcrobotAction {
+    pub fn torque(&self) -> f64 {
+        match self {
+            AA::Negative => -1f64,
+            AA::Zero => 0f64,
+            AA::Positive => 1f64,
+        }
+    }
+}
+
+impl Acrobot {
+    pub fn new(method: ExMethod, substeps: usize, time_limit: Option<usize>) -> Self {
+        assert!(substeps >= 1, "substeps must be at least 1");
+        Acrobot {
+            method,
+            substeps,
+            time_limit,
+        }
+    }
+
+    pub fn get_method(&self) -> ExMethod {
+        self.method
+    }
+
+    pub fn get_substeps(&self) -> usize {
+        self.substeps
+    }
+
+    pub fn get_time_limit(&self) -> Option<usize> {
+        self.time_limit
+    }
+
+    /// Angle and angular velocity bounds
+    pub fn state_range(&self) -> StateRange {
+        StateRange::new(
+            vec![-PI, -PI, -MAX_VEL_1, -MAX_VEL_2],
+            vec![PI, PI, MAX_VEL_1, MAX_VEL_2],
+        )
+    }
+}
+
+impl Default for Acrobot {
+    /// RK4 with four substeps and a 500-step time limit
+    fn default() -> Self {
+        Acrobot::new(ExMethod::RK4, 4, Some(500))
+    }
+}
+
+fn dynamics(st: &mut State<f64>, input: &ControlInput) {
+    let (theta1, theta2, dtheta1, dtheta2) = (st.value[0], st.value[1], st.value[2], st.value[3]);
+    let (m1, m2, l1, lc, i) = (LINK_MASS_1, LINK_MASS_2, LINK_LENGTH_1, LINK_COM, LINK_MOI);
+
+    let d1 =
+        m1 * lc.powi(2) + m2 * (l1.powi(2) + lc.powi(2) + 2.0 * l1 * lc * theta2.cos()) + 2.0 * i;
+    let d2 = m2 * (lc.powi(2) + l1 * lc * theta2.cos()) + i;
+    let phi2 = m2 * lc * GRAVITY * (theta1 + theta2 - PI / 2.0).cos();
+    let phi1 = -m2 * l1 * lc * dtheta2.powi(2) * theta2.sin()
+        - 2.0 * m2 * l1 * lc * dtheta2 * dtheta1 * theta2.sin()
+        + (m1 * lc + m2 * l1) * GRAVITY * (theta1 - PI / 2.0).cos()
+        + phi2;
+    let ddtheta2 =
+        (input.u + d2 / d1 * phi1 - m2 * l1 * lc * dtheta1.powi(2) * theta2.sin() - phi2)
+            / (m2 * lc.powi(2) + i - d2.powi(2) / d1);
+    let ddtheta1 = -(d2 * ddtheta2 + phi1) / d1;
+    st.deriv = vec![dtheta1, dtheta2, ddtheta1, ddtheta2];
+}
+
+/// Wrap an angle into [-π, π)
+fn wrap(theta: f64) -> f64 {
+    (theta + PI).rem_euclid(2.0 * PI) - PI
+}
+
+impl MarkovDecisionProcess<[f64; 4], AcrobotAction> for Acrobot {
+    /// Continuous state space: there is nothing to enumerate
+    fn states(&self) -> Vec<[f64; 4]> {
+        vec![]
+    }
+
+    fn actions(&self) -> Vec<AcrobotAction> {
+        vec![AA::Negative, AA::Zero, AA::Positive]
+    }
+
+    fn actions_at(&self, _state: &[f64; 4]) -> Vec<AcrobotAction> {
+        self.actions()
+    }
+
+    fn reward(&self, _state: &[f64; 4], _action: &AcrobotAction) -> f64 {
+        -1f64
+    }
+
+    fn transition(&self, state: &[f64; 4], action: &AcrobotAction) -> Option<[f64; 4]> {
+        let input = ControlInput { u: action.torque() };
+        let y = integrate(dynamics, input, state, DT, self.substeps, self.method);
+        let (theta1, theta2) = (wrap(y[0]), wrap(y[1]));
+        if -theta1.cos() - (theta1 + theta2).cos() > 1f64 {
+            None
+        } else {
+            Some([
+                theta1,
+                theta2,
+                y[2].clamp(-MAX_VEL_1, MAX_VEL_1),
+                y[3].clamp(-MAX_VEL_2, MAX_VEL_2),
+            ])
+        }
+    }
+}
+
+impl EpisodicProcess<[f64; 4], AcrobotAction> for Acrobot {
+    fn init_state(&self, rng: &mut dyn RngCore) -> [f64; 4] {
+        [(); 4].map(|_| rng.gen_range(-0.1..0.1))
+    }
+
+    fn time_limit(&self) -> Option<usize> {
+        self.time_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angles_wrap_into_half_open_range() {
+        assert_eq!(wrap(PI), -PI);
+        assert_eq!(wrap(-PI), -PI);
+        assert!((wrap(1.5 * PI) + 0.5 * PI).abs() < 1e-12);
+        assert!((wrap(-2.5 * PI) + 0.5 * PI).abs() < 1e-12);
+
+        // θ2 swings past π and comes back in near -π
+        let env = Acrobot::default();
+        let next = env
+            .transition(&[0.0, PI - 0.01, 0.0, 2.0], &AA::Zero)
+            .unwrap();
+        assert!((-PI..-PI / 2.0).contains(&next[1]), "θ2 = {}", next[1]);
+    }
+
+    #[test]
+    fn velocities_are_clipped() {
+        let env = Acrobot::default();
+        let next = env
+            .transition(&[0.0, 0.0, 40.0, -60.0], &AA::Positive)
+            .unwrap();
+        assert_eq!(next[2], MAX_VEL_1);
+        assert_eq!(next[3], -MAX_VEL_2);
+        assert!(next[..2].iter().all(|theta| (-PI..PI).contains(theta)));
+    }
+
+    #[test]
+    fn swing_up_is_terminal() {
+        let env = Acrobot::default();
+        assert_eq!(env.transition(&[PI, 0.0, 0.0, 0.0], &AA::Zero), None);
+        assert_eq!(env.step(&[PI, 0.0, 0.0, 0.0], &AA::Zero), (None, -1.0));
+        // Hanging at rest stays put
+        let next = env.transition(&[0.0; 4], &AA::Zero).unwrap();
+        assert!(next.iter().all(|x| x.abs() < 1e-12), "{:?}", next);
+    }
+}
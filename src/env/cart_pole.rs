@@ -0,0 +1,166 @@
+use super::dynamics::{integrate, ControlInput};
+use crate::base::features::StateRange;
+use crate::base::process::{EpisodicProcess, MarkovDecisionProcess};
+use peroxide::fuga::*;
+
+const GRAVITY: f64 = 9.8;
+const CART_MASS: f64 = 1.0;
+const POLE_MASS: f64 = 0.1;
+/// Half the pole length
+const POLE_LENGTH: f64 = 0.5;
+const FORCE: f64 = 10.0;
+const TAU: f64 = 0.02;
+const X_THRESHOLD: f64 = 2.4;
+const THETA_THRESHOLD: f64 = 12.0 * std::f64::consts::PI / 180.0;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Cart-Pole
+// └──────────────────────────────────────────────────────────┘
+/// Cart-Pole balancing of Barto, Sutton & Anderson (1983)
+///
+/// State `[x, ẋ, θ, θ̇]`. A force of ±10 N is held for 0.02 s while the frictionless
+/// equations of motion are integrated with `method`. Every step yields +1; the episode
+/// terminates once |x| > 2.4 or |θ| > 12°. Episodes start with every component uniform in
+/// [-0.05, 0.05].
+#[derive(Debug, Clone)]
+pub struct CartPole {
+    method: ExMethod,
+    substeps: usize,
+    time_limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CartPoleAction {
+    Left,
+    Right,
+}
+
+impl CartPoleAction {
+    pub fn force(&self) -> f64 {
+        match self {
+            CartPoleAction::Left => -FORCE,
+            CartPoleAction::Right => FORCE,
+        }
+    }
+}
+
+impl CartPole {
+    pub fn new(method: ExMethod, substeps: usize, time_limit: Option<usize>) -> Self {
+        assert!(substeps >= 1, "substeps must be at least 1");
+        CartPole {
+            method,
+            substeps,
+            time_limit,
+        }
+    }
+
+    pub fn get_method(&self) -> ExMethod {
+        self.method
+    }
+
+    pub fn get_substeps(&self) -> usize {
+        self.substeps
+    }
+
+    pub fn get_time_limit(&self) -> Option<usize> {
+        self.time_limit
+    }
+
+    /// Termination bounds for x and θ, nominal bounds for the (unbounded) velocities
+    pub fn state_range(&self) -> StateRange {
+        StateRange::new(
+            vec![-X_THRESHOLD, -3.0, -THETA_THRESHOLD, -3.5],
+            vec![X_THRESHOLD, 3.0, THETA_THRESHOLD, 3.5],
+        )
+    }
+}
+
+impl Default for CartPole {
+    /// RK4 with one substep and a 500-step time limit
+    fn default() -> Self {
+        CartPole::new(ExMethod::RK4, 1, Some(500))
+    }
+}
+
+fn dynamics(st: &mut State<f64>, input: &ControlInput) {
+    let (x_dot, theta, theta_dot) = (st.value[1], st.value[2], st.value[3]);
+    let total_mass = CART_MASS + POLE_MASS;
+    let pole_mass_length = POLE_MASS * POLE_LENGTH;
+    let (sin, cos) = theta.sin_cos();
+
+    let temp = (input.u + pole_mass_length * theta_dot.powi(2) * sin) / total_mass;
+    let theta_acc = (GRAVITY * sin - cos * temp)
+        / (POLE_LENGTH * (4.0 / 3.0 - POLE_MASS * cos.powi(2) / total_mass));
+    let x_acc = temp - pole_mass_length * theta_acc * cos / total_mass;
+    st.deriv = vec![x_dot, x_acc, theta_dot, theta_acc];
+}
+
+impl MarkovDecisionProcess<[f64; 4], CartPoleAction> for CartPole {
+    /// Continuous state space: there is nothing to enumerate
+    fn states(&self) -> Vec<[f64; 4]> {
+        vec![]
+    }
+
+    fn actions(&self) -> Vec<CartPoleAction> {
+        vec![CartPoleAction::Left, CartPoleAction::Right]
+    }
+
+    fn actions_at(&self, _state: &[f64; 4]) -> Vec<CartPoleAction> {
+        self.actions()
+    }
+
+    fn reward(&self, _state: &[f64; 4], _action: &CartPoleAction) -> f64 {
+        1f64
+    }
+
+    fn transition(&self, state: &[f64; 4], action: &CartPoleAction) -> Option<[f64; 4]> {
+        let input = ControlInput { u: action.force() };
+        let y = integrate(dynamics, input, state, TAU, self.substeps, self.method);
+        if y[0].abs() > X_THRESHOLD || y[2].abs() > THETA_THRESHOLD {
+            None
+        } else {
+            Some([y[0], y[1], y[2], y[3]])
+        }
+    }
+}
+
+impl EpisodicProcess<[f64; 4], CartPoleAction> for CartPole {
+    fn init_state(&self, rng: &mut dyn RngCore) -> [f64; 4] {
+        [(); 4].map(|_| rng.gen_range(-0.05..0.05))
+    }
+
+    fn time_limit(&self) -> Option<usize> {
+        self.time_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CartPoleAction as CPA;
+
+    #[test]
+    fn falling_pole_is_terminal() {
+        let env = CartPole::default();
+        assert_eq!(env.step(&[0.0, 0.0, 0.2, 1.0], &CPA::Right), (None, 1.0));
+        assert_eq!(env.transition(&[0.0, 0.0, -0.2, -1.0], &CPA::Left), None);
+        assert!(env.transition(&[0.0, 0.0, 0.2, -1.0], &CPA::Left).is_some());
+    }
+
+    #[test]
+    fn leaving_the_track_is_terminal() {
+        let env = CartPole::default();
+        assert_eq!(env.transition(&[2.39, 1.0, 0.0, 0.0], &CPA::Right), None);
+        assert_eq!(env.transition(&[-2.39, -1.0, 0.0, 0.0], &CPA::Left), None);
+        assert!(env.transition(&[2.3, 1.0, 0.0, 0.0], &CPA::Right).is_some());
+    }
+
+    #[test]
+    fn upright_pole_at_rest_stays_put() {
+        let env = CartPole::default();
+        let next = env.transition(&[0.0; 4], &CPA::Right).unwrap();
+        // The push moves the cart right and tips the pole left
+        assert!(next[0] > 0.0 && next[1] > 0.0);
+        assert!(next[2] < 0.0 && next[3] < 0.0);
+    }
+}
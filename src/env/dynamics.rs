@@ -0,0 +1,84 @@
+use peroxide::fuga::*;
+
+/// Integrate `f` from `init` over one control interval `dt` with `substeps` steps of `method`
+///
+/// `input` carries whatever is held constant over the interval (e.g. the applied force).
+/// The steps are taken here rather than with `ExplicitODE`, whose RK4 evaluates its last
+/// stage at y + h k3 + h/2 k1 and so is only first-order accurate.
+pub(crate) fn integrate<E>(
+    f: fn(&mut State<f64>, &E),
+    input: E,
+    init: &[f64],
+    dt: f64,
+    substeps: usize,
+    method: ExMethod,
+) -> Vec<f64> {
+    let h = dt / substeps as f64;
+    let mut st = State::<f64>::new(0f64, init.to_vec(), vec![0f64; init.len()]);
+    for n in 0..substeps {
+        let t = n as f64 * h;
+        let y = st.value.clone();
+        // f(t, y)
+        let mut deriv = |t: f64, y: Vec<f64>| {
+            st.param = t;
+            st.value = y;
+            f(&mut st, &input);
+            st.deriv.clone()
+        };
+        let k1 = deriv(t, y.clone());
+        let y_next = match method {
+            ExMethod::Euler => y.add_vec(&k1.mul_scalar(h)),
+            ExMethod::RK4 => {
+                let k2 = deriv(t + h / 2f64, y.add_vec(&k1.mul_scalar(h / 2f64)));
+                let k3 = deriv(t + h / 2f64, y.add_vec(&k2.mul_scalar(h / 2f64)));
+                let k4 = deriv(t + h, y.add_vec(&k3.mul_scalar(h)));
+                let k = k1
+                    .add_vec(&k2.mul_scalar(2f64))
+                    .add_vec(&k3.mul_scalar(2f64))
+                    .add_vec(&k4);
+                y.add_vec(&k.mul_scalar(h / 6f64))
+            }
+        };
+        st.param = t + h;
+        st.value = y_next;
+    }
+    st.value
+}
+
+/// Constant control input of a continuous-time system
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ControlInput {
+    pub(crate) u: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ẏ = u y
+    fn growth(st: &mut State<f64>, input: &ControlInput) {
+        st.deriv = vec![input.u * st.value[0]];
+    }
+
+    #[test]
+    fn one_step_matches_hand_computed_euler_and_rk4() {
+        let input = ControlInput { u: 2.0 };
+        let h = 0.1;
+        let z = input.u * h;
+
+        let euler = integrate(growth, input, &[3.0], h, 1, ExMethod::Euler);
+        assert!((euler[0] - 3.0 * (1.0 + z)).abs() < 1e-12);
+
+        // RK4 on a linear ODE is the 4th-order Taylor polynomial of exp(z)
+        let rk4 = integrate(growth, input, &[3.0], h, 1, ExMethod::RK4);
+        let taylor = 1.0 + z + z.powi(2) / 2.0 + z.powi(3) / 6.0 + z.powi(4) / 24.0;
+        assert!((rk4[0] - 3.0 * taylor).abs() < 1e-12);
+    }
+
+    #[test]
+    fn substeps_split_the_interval() {
+        let input = ControlInput { u: 2.0 };
+        let euler = integrate(growth, input, &[3.0], 0.1, 4, ExMethod::Euler);
+        assert!((euler[0] - 3.0 * 1.05f64.powi(4)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,379 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Grid Map
+// └──────────────────────────────────────────────────────────┘
+/// Cell of a text map
+///
+/// | char | cell |
+/// |------|------|
+/// | `.` | `Empty` |
+/// | `S` | `Start` |
+/// | `G` | `Goal` |
+/// | `#` | `Wall` |
+/// | `X` | `Pit` |
+/// | `0`-`9` | `Reward(d)` |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapCell {
+    Empty,
+    Start,
+    Goal,
+    Wall,
+    Pit,
+    /// Empty cell with a custom reward for entering it
+    Reward(u8),
+}
+
+impl MapCell {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(MapCell::Empty),
+            'S' => Some(MapCell::Start),
+            'G' => Some(MapCell::Goal),
+            '#' => Some(MapCell::Wall),
+            'X' => Some(MapCell::Pit),
+            '0'..='9' => Some(MapCell::Reward(c as u8 - b'0')),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(&self) -> char {
+        match self {
+            MapCell::Empty => '.',
+            MapCell::Start => 'S',
+            MapCell::Goal => 'G',
+            MapCell::Wall => '#',
+            MapCell::Pit => 'X',
+            MapCell::Reward(d) => (b'0' + (*d).min(9)) as char,
+        }
+    }
+}
+
+/// Rectangular ASCII layout of a grid world
+///
+/// The first line is the top row: line i, column j is the cell (x, y) = (j, num_y - 1 - i),
+/// matching `GridWorldAction::Up` increasing y. A map has exactly one start and at least one
+/// goal. Leading and trailing blank lines are ignored, while error positions still count them.
+/// `Display` emits the same format, one row per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridMap {
+    num_x: usize,
+    num_y: usize,
+    cells: Vec<MapCell>,
+}
+
+impl GridMap {
+    /// All-empty map
+    pub fn new(num_x: usize, num_y: usize) -> Self {
+        assert!(num_x > 0 && num_y > 0, "Map must not be empty");
+        GridMap {
+            num_x,
+            num_y,
+            cells: vec![MapCell::Empty; num_x * num_y],
+        }
+    }
+
+    pub fn get_num_x(&self) -> usize {
+        self.num_x
+    }
+
+    pub fn get_num_y(&self) -> usize {
+        self.num_y
+    }
+
+    pub fn get(&self, state: &(usize, usize)) -> MapCell {
+        self.cells[self.index(state)]
+    }
+
+    pub fn set(&mut self, state: &(usize, usize), cell: MapCell) {
+        let i = self.index(state);
+        self.cells[i] = cell;
+    }
+
+    /// Every `(x, y)` holding a cell for which `pred` is true, in x-major order
+    pub fn find(&self, pred: impl Fn(&MapCell) -> bool) -> Vec<(usize, usize)> {
+        (0..self.num_x)
+            .flat_map(|x| (0..self.num_y).map(move |y| (x, y)))
+            .filter(|s| pred(&self.get(s)))
+            .collect()
+    }
+
+    pub fn start(&self) -> Option<(usize, usize)> {
+        self.find(|c| *c == MapCell::Start).into_iter().next()
+    }
+
+    pub fn goals(&self) -> Vec<(usize, usize)> {
+        self.find(|c| *c == MapCell::Goal)
+    }
+
+    pub fn from_file(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        let text = std::fs::read_to_string(file_path)?;
+        Ok(text.parse()?)
+    }
+
+    pub fn write_file(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::write(file_path, self.to_string())?;
+        Ok(())
+    }
+
+    fn index(&self, &(x, y): &(usize, usize)) -> usize {
+        assert!(x < self.num_x && y < self.num_y, "Cell out of the map");
+        y * self.num_x + x
+    }
+}
+
+impl FromStr for GridMap {
+    type Err = MapParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().map(|l| l.trim_end()).collect::<Vec<_>>();
+        let Some(skip) = lines.iter().position(|l| !l.is_empty()) else {
+            return Err(MapParseError::new(1, 1, MapParseErrorKind::Empty));
+        };
+        let num_lines = lines.iter().rposition(|l| !l.is_empty()).unwrap() + 1;
+        let lines = &lines[skip..num_lines];
+        let first = lines[0];
+        let num_x = first.chars().count();
+        let num_y = lines.len();
+
+        let mut map = GridMap::new(num_x, num_y);
+        let mut start: Option<(usize, usize)> = None;
+        for (i, line) in lines.iter().enumerate() {
+            let y = num_y - 1 - i;
+            let mut width = 0;
+            for (j, c) in line.chars().enumerate() {
+                width = j + 1;
+                if j >= num_x {
+                    return Err(MapParseError::new(
+                        skip + i + 1,
+                        j + 1,
+                        MapParseErrorKind::RowLength {
+                            expected: num_x,
+                            found: line.chars().count(),
+                        },
+                    ));
+                }
+                let cell = MapCell::from_char(c).ok_or(MapParseError::new(
+                    skip + i + 1,
+                    j + 1,
+                    MapParseErrorKind::UnexpectedChar(c),
+                ))?;
+                if cell == MapCell::Start {
+                    if let Some((x0, y0)) = start {
+                        return Err(MapParseError::new(
+                            skip + i + 1,
+                            j + 1,
+                            MapParseErrorKind::DuplicateStart {
+                                line: skip + num_y - y0,
+                                column: x0 + 1,
+                            },
+                        ));
+                    }
+                    start = Some((j, y));
+                }
+                map.set(&(j, y), cell);
+            }
+            if width < num_x {
+                return Err(MapParseError::new(
+                    skip + i + 1,
+                    width + 1,
+                    MapParseErrorKind::RowLength {
+                        expected: num_x,
+                        found: width,
+                    },
+                ));
+            }
+        }
+
+        // Whole-map errors point just past the last cell
+        let end = (skip + num_y, num_x + 1);
+        if start.is_none() {
+            return Err(MapParseError::new(
+                end.0,
+                end.1,
+                MapParseErrorKind::MissingStart,
+            ));
+        }
+        if map.goals().is_empty() {
+            return Err(MapParseError::new(
+                end.0,
+                end.1,
+                MapParseErrorKind::MissingGoal,
+            ));
+        }
+        Ok(map)
+    }
+}
+
+impl fmt::Display for GridMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in (0..self.num_y).rev() {
+            let row = (0..self.num_x)
+                .map(|x| self.get(&(x, y)).to_char())
+                .collect::<String>();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Parse Error
+// └──────────────────────────────────────────────────────────┘
+/// Map parse failure at a 1-based `line` and `column`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapParseError {
+    line: usize,
+    column: usize,
+    kind: MapParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapParseErrorKind {
+    Empty,
+    UnexpectedChar(char),
+    /// Row width differs from the first row
+    RowLength {
+        expected: usize,
+        found: usize,
+    },
+    /// Second `S`; `line`/`column` locate the first one
+    DuplicateStart {
+        line: usize,
+        column: usize,
+    },
+    MissingStart,
+    MissingGoal,
+}
+
+impl MapParseError {
+    pub fn new(line: usize, column: usize, kind: MapParseErrorKind) -> Self {
+        MapParseError { line, column, kind }
+    }
+
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    pub fn get_kind(&self) -> &MapParseErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            MapParseErrorKind::Empty => write!(f, "empty map"),
+            MapParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            MapParseErrorKind::RowLength { expected, found } => {
+                write!(f, "row has {} cells, expected {}", found, expected)
+            }
+            MapParseErrorKind::DuplicateStart { line, column } => write!(
+                f,
+                "second start cell (first at line {}, column {})",
+                line, column
+            ),
+            MapParseErrorKind::MissingStart => write!(f, "no start cell 'S'"),
+            MapParseErrorKind::MissingGoal => write!(f, "no goal cell 'G'"),
+        }
+    }
+}
+
+impl Error for MapParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(s: &str) -> MapParseError {
+        s.parse::<GridMap>().unwrap_err()
+    }
+
+    #[test]
+    fn parses_top_row_first() {
+        let map: GridMap = "..G\nS#X\n".parse().unwrap();
+        assert_eq!((map.get_num_x(), map.get_num_y()), (3, 2));
+        assert_eq!(map.start(), Some((0, 0)));
+        assert_eq!(map.goals(), vec![(2, 1)]);
+        assert_eq!(map.get(&(1, 0)), MapCell::Wall);
+        assert_eq!(map.get(&(2, 0)), MapCell::Pit);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let text = "S.3\n#XG\n";
+        let map: GridMap = text.parse().unwrap();
+        assert_eq!(map.get(&(2, 1)), MapCell::Reward(3));
+        assert_eq!(map.to_string(), text);
+    }
+
+    #[test]
+    fn skips_blank_lines_around_the_map() {
+        let map: GridMap = "\n  \nS.G\n\n".parse().unwrap();
+        assert_eq!((map.get_num_x(), map.get_num_y()), (3, 1));
+
+        let err = parse_err("\n\nS?G\n");
+        assert_eq!((err.get_line(), err.get_column()), (3, 2));
+        assert_eq!(err.get_kind(), &MapParseErrorKind::UnexpectedChar('?'));
+    }
+
+    #[test]
+    fn empty_map() {
+        for s in ["", "\n", " \n\t\n"] {
+            let err = parse_err(s);
+            assert_eq!((err.get_line(), err.get_column()), (1, 1));
+            assert_eq!(err.get_kind(), &MapParseErrorKind::Empty);
+        }
+    }
+
+    #[test]
+    fn row_length_errors() {
+        let err = parse_err("S.G\n..\n");
+        assert_eq!((err.get_line(), err.get_column()), (2, 3));
+        assert_eq!(
+            err.get_kind(),
+            &MapParseErrorKind::RowLength {
+                expected: 3,
+                found: 2
+            }
+        );
+
+        let err = parse_err("S.G\n....\n");
+        assert_eq!((err.get_line(), err.get_column()), (2, 4));
+        assert_eq!(
+            err.get_kind(),
+            &MapParseErrorKind::RowLength {
+                expected: 3,
+                found: 4
+            }
+        );
+    }
+
+    #[test]
+    fn duplicate_start_locates_both() {
+        let err = parse_err(".S.\nG.S\n");
+        assert_eq!((err.get_line(), err.get_column()), (2, 3));
+        assert_eq!(
+            err.get_kind(),
+            &MapParseErrorKind::DuplicateStart { line: 1, column: 2 }
+        );
+    }
+
+    #[test]
+    fn missing_cells_point_past_the_end() {
+        let err = parse_err("...\n.G.\n");
+        assert_eq!((err.get_line(), err.get_column()), (2, 4));
+        assert_eq!(err.get_kind(), &MapParseErrorKind::MissingStart);
+
+        let err = parse_err("S..\n...\n");
+        assert_eq!(err.get_kind(), &MapParseErrorKind::MissingGoal);
+        assert_eq!(err.to_string(), "line 2, column 4: no goal cell 'G'");
+    }
+}
@@ -1,19 +1,48 @@
-use crate::base::process::MarkovDecisionProcess;
+use super::grid_map::{GridMap, MapCell};
+use crate::base::features::StateRange;
+use crate::base::process::{EpisodicProcess, ExploringStarts, MarkovDecisionProcess};
+use peroxide::fuga::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
 use GridWorldAction as GWA;
 
 // ┌──────────────────────────────────────────────────────────┐
 //  Grid World
 // └──────────────────────────────────────────────────────────┘
+/// Grid world with walls, pits and goals
+///
+/// Moving into a wall is a bump: the agent stays in place. Moving off the grid is a bump as
+/// well and either stays in place or terminates, depending on `BoundaryBehavior`. Entering a
+/// goal terminates the episode; entering a pit terminates it or sends the agent back to the
+/// start, depending on `PitBehavior`.
+///
+/// Moves are deterministic unless the grid is slippery or windy. A slippery move goes to
+/// either perpendicular direction with probability `slip / 2` each, as in FrozenLake. After
+/// the move, the wind of the starting column pushes the agent that many cells up, stopping at
+/// walls and the top edge, and only the final cell can end the episode, as in Windy
+/// Gridworld. Stochastic wind is one weaker, as given or one stronger with probability 1/3
+/// each in windy columns. `transition_probs` is exact, while `transition` and `reward` give
+/// the nominal outcome: the intended move under the given wind.
+///
+/// Rewards follow a `RewardSpec`, by default +1 for a goal, -1 for a pit or a bump and 0
+/// otherwise. Walls, goals and pits are not states of the process.
 #[derive(Debug, Clone)]
 pub struct GridWorld {
     num_x: usize,
     num_y: usize,
     init_state: (usize, usize),
-    goal_state: (usize, usize),
-    terminal_states: Vec<(usize, usize)>,
+    cells: Vec<GridCell>,
+    boundary: BoundaryBehavior,
+    pits: PitBehavior,
+    rewards: RewardSpec,
+    slip: f64,
+    wind: Vec<usize>,
+    wind_mode: WindMode,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GridWorldAction {
     Up,
     Down,
@@ -21,22 +50,197 @@ pub enum GridWorldAction {
     Right,
 }
 
+impl GridWorldAction {
+    /// The two directions a slippery move can deviate to
+    pub fn perpendicular(&self) -> [GridWorldAction; 2] {
+        match self {
+            GWA::Up | GWA::Down => [GWA::Left, GWA::Right],
+            GWA::Left | GWA::Right => [GWA::Up, GWA::Down],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GridCell {
+    Empty,
+    /// Blocks movement
+    Wall,
+    /// Penalized, then terminates or restarts, see `PitBehavior`
+    Pit,
+    /// Terminates with a reward
+    Goal,
+}
+
+impl GridCell {
+    /// Whether entering the cell ends the move (goals and pits)
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, GridCell::Pit | GridCell::Goal)
+    }
+}
+
+/// How the wind strength of a column is applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindMode {
+    /// Exactly the column's strength
+    Steady,
+    /// The column's strength -1, +0 or +1 with probability 1/3 each, in windy columns only
+    Stochastic,
+}
+
+/// What a move off the grid does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundaryBehavior {
+    /// Bump and stay in place
+    Stay,
+    /// Bump and end the episode
+    Terminate,
+}
+
+/// What entering a pit does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PitBehavior {
+    /// End the episode
+    Terminate,
+    /// Go back to the initial state and continue, like the cliff of Cliff Walking
+    Restart,
+}
+
 impl GridWorld {
-    pub fn new(
+    /// Grid with a single goal, the given walls, `BoundaryBehavior::Stay` and
+    /// `PitBehavior::Terminate`
+    ///
+    /// Cells in `walls` block movement; they are not terminal states. Use `set_cell` with
+    /// `GridCell::Pit` for cells that end the episode.
+    pub fn with_walls(
         num_x: usize,
         num_y: usize,
         init_state: (usize, usize),
         goal_state: (usize, usize),
-        terminal_states: Vec<(usize, usize)>,
+        walls: Vec<(usize, usize)>,
     ) -> Self {
-        GridWorld {
+        let mut env = GridWorld {
             num_x,
             num_y,
             init_state,
-            goal_state,
-            terminal_states,
+            cells: vec![GridCell::Empty; num_x * num_y],
+            boundary: BoundaryBehavior::Stay,
+            pits: PitBehavior::Terminate,
+            rewards: RewardSpec::default(),
+            slip: 0.0,
+            wind: vec![0; num_x],
+            wind_mode: WindMode::Steady,
+        };
+        for s in walls.iter() {
+            env.set_cell(s, GridCell::Wall);
+        }
+        env.set_cell(&goal_state, GridCell::Goal);
+        env
+    }
+
+    /// Grid world from a text map (`BoundaryBehavior::Stay`, `PitBehavior::Terminate`)
+    ///
+    /// A digit cell `d` is an empty cell whose entry reward is `d`; every other reward is the
+    /// `RewardSpec` default.
+    pub fn from_map(map: &GridMap) -> Result<Self, Box<dyn Error>> {
+        let init_state = map.start().ok_or("map has no start")?;
+        let mut env = GridWorld {
+            num_x: map.get_num_x(),
+            num_y: map.get_num_y(),
+            init_state,
+            cells: vec![GridCell::Empty; map.get_num_x() * map.get_num_y()],
+            boundary: BoundaryBehavior::Stay,
+            pits: PitBehavior::Terminate,
+            rewards: RewardSpec::default(),
+            slip: 0.0,
+            wind: vec![0; map.get_num_x()],
+            wind_mode: WindMode::Steady,
+        };
+        for x in 0..env.num_x {
+            for y in 0..env.num_y {
+                let cell = match map.get(&(x, y)) {
+                    MapCell::Wall => GridCell::Wall,
+                    MapCell::Pit => GridCell::Pit,
+                    MapCell::Goal => GridCell::Goal,
+                    MapCell::Reward(d) => {
+                        env.rewards.set_cell_reward((x, y), d as f64);
+                        GridCell::Empty
+                    }
+                    MapCell::Empty | MapCell::Start => GridCell::Empty,
+                };
+                env.set_cell(&(x, y), cell);
+            }
         }
+        Ok(env)
     }
+
+    /// Windy Gridworld of Sutton & Barto Example 6.5 (Exercise 6.10 with stochastic wind)
+    ///
+    /// 10x7 grid from (0, 3) to the goal (7, 3) with wind `[0, 0, 0, 1, 1, 1, 2, 2, 1, 0]` and
+    /// -1 for every step, the last one included.
+    pub fn windy(wind_mode: WindMode) -> Self {
+        let mut env = GridWorld::with_walls(10, 7, (0, 3), (7, 3), vec![]);
+        env.set_wind(vec![0, 0, 0, 1, 1, 1, 2, 2, 1, 0], wind_mode);
+        env.set_reward_spec(RewardSpec::new(-1.0, -1.0, -1.0, -1.0));
+        env
+    }
+
+    /// 4x4 FrozenLake: every move slips with probability 2/3, holes are pits and only the
+    /// goal pays (+1)
+    pub fn frozen_lake() -> Self {
+        let map = "S...\n.X.X\n...X\nX..G\n"
+            .parse()
+            .expect("Invalid FrozenLake map");
+        let mut env = GridWorld::from_map(&map).expect("Invalid FrozenLake map");
+        env.set_slip(2.0 / 3.0);
+        env.set_reward_spec(RewardSpec::new(0.0, 0.0, 0.0, 1.0));
+        env
+    }
+
+    /// Cliff Walking of Sutton & Barto Example 6.6
+    ///
+    /// 12x4 grid from (0, 0) to the goal (11, 0) along a cliff of pits (1..=10, 0). Every step
+    /// costs -1, the last one included; stepping into the cliff costs -100 and restarts from
+    /// (0, 0) instead of ending the episode.
+    pub fn cliff_walking() -> Self {
+        let mut env = GridWorld::with_walls(12, 4, (0, 0), (11, 0), vec![]);
+        for x in 1..=10 {
+            env.set_cell(&(x, 0), GridCell::Pit);
+        }
+        env.set_pit_behavior(PitBehavior::Restart);
+        env.set_reward_spec(RewardSpec::new(-1.0, -1.0, -100.0, -1.0));
+        env
+    }
+
+    /// Parse a text map file, see `GridMap`
+    pub fn from_map_file(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        GridWorld::from_map(&GridMap::from_file(file_path)?)
+    }
+
+    /// Text map of this grid world
+    ///
+    /// Only entry rewards of empty cells that are integers in 0..=9 survive, as digit cells.
+    pub fn to_map(&self) -> GridMap {
+        let mut map = GridMap::new(self.num_x, self.num_y);
+        for x in 0..self.num_x {
+            for y in 0..self.num_y {
+                let cell = match self.get_cell(&(x, y)) {
+                    GridCell::Empty => match self.rewards.get_cell_reward(&(x, y)) {
+                        Some(r) if r.fract() == 0.0 && (0.0..=9.0).contains(&r) => {
+                            MapCell::Reward(r as u8)
+                        }
+                        _ => MapCell::Empty,
+                    },
+                    GridCell::Wall => MapCell::Wall,
+                    GridCell::Pit => MapCell::Pit,
+                    GridCell::Goal => MapCell::Goal,
+                };
+                map.set(&(x, y), cell);
+            }
+        }
+        map.set(&self.init_state, MapCell::Start);
+        map
+    }
+
     pub fn get_num_x(&self) -> usize {
         self.num_x
     }
@@ -46,57 +250,370 @@ impl GridWorld {
     pub fn get_init_state(&self) -> (usize, usize) {
         self.init_state
     }
-    pub fn get_goal_state(&self) -> (usize, usize) {
-        self.goal_state
+    pub fn get_boundary(&self) -> BoundaryBehavior {
+        self.boundary
     }
-    pub fn get_terminal_states(&self) -> Vec<(usize, usize)> {
-        self.terminal_states.clone()
+
+    pub fn set_boundary(&mut self, boundary: BoundaryBehavior) {
+        self.boundary = boundary;
     }
-}
 
-impl MarkovDecisionProcess<(usize, usize), GridWorldAction> for GridWorld {
-    fn states(&self) -> Vec<(usize, usize)> {
-        let mut states = Vec::new();
+    pub fn get_pit_behavior(&self) -> PitBehavior {
+        self.pits
+    }
 
-        for x in 0..self.num_x {
-            for y in 0..self.num_y {
-                states.push((x, y))
+    pub fn set_pit_behavior(&mut self, pits: PitBehavior) {
+        self.pits = pits;
+    }
+
+    pub fn get_slip(&self) -> f64 {
+        self.slip
+    }
+
+    /// Probability of moving perpendicular to the intended direction
+    pub fn set_slip(&mut self, slip: f64) {
+        assert!((0.0..=1.0).contains(&slip), "slip must be in [0, 1]");
+        self.slip = slip;
+    }
+
+    pub fn get_wind(&self) -> &[usize] {
+        &self.wind
+    }
+
+    pub fn get_wind_mode(&self) -> WindMode {
+        self.wind_mode
+    }
+
+    /// Upward wind strength of every column, `wind[x]` for column x
+    pub fn set_wind(&mut self, wind: Vec<usize>, wind_mode: WindMode) {
+        assert_eq!(wind.len(), self.num_x, "wind needs one strength per column");
+        self.wind = wind;
+        self.wind_mode = wind_mode;
+    }
+
+    pub fn get_reward_spec(&self) -> &RewardSpec {
+        &self.rewards
+    }
+
+    pub fn set_reward_spec(&mut self, rewards: RewardSpec) {
+        self.rewards = rewards;
+    }
+
+    pub fn get_cell(&self, state: &(usize, usize)) -> GridCell {
+        self.cells[self.index(state)]
+    }
+
+    pub fn set_cell(&mut self, state: &(usize, usize), cell: GridCell) {
+        let i = self.index(state);
+        self.cells[i] = cell;
+    }
+
+    /// Every cell of kind `cell`, in x-major order
+    pub fn find_cells(&self, cell: GridCell) -> Vec<(usize, usize)> {
+        self.states_where(|c| *c == cell)
+    }
+
+    pub fn get_goals(&self) -> Vec<(usize, usize)> {
+        self.find_cells(GridCell::Goal)
+    }
+
+    pub fn get_walls(&self) -> Vec<(usize, usize)> {
+        self.find_cells(GridCell::Wall)
+    }
+
+    pub fn get_pits(&self) -> Vec<(usize, usize)> {
+        self.find_cells(GridCell::Pit)
+    }
+
+    /// Cells as continuous coordinates: cell (x, y) is the center of [x - ½, x + ½] × [y - ½, y + ½]
+    pub fn coordinate_range(&self) -> StateRange {
+        StateRange::new(
+            vec![-0.5, -0.5],
+            vec![self.num_x as f64 - 0.5, self.num_y as f64 - 0.5],
+        )
+    }
+
+    /// Write `{prefix}-goal.parquet` (goals) and `{prefix}-terminal.parquet` (walls and pits)
+    /// for plotting
+    pub fn write_layout_parquet(&self, prefix: &str) -> Result<(), Box<dyn Error>> {
+        let goals = self.get_goals();
+        let mut df = DataFrame::new(vec![]);
+        df.push(
+            "goal_x",
+            Series::new(goals.iter().map(|s| s.0 as u64).collect()),
+        );
+        df.push(
+            "goal_y",
+            Series::new(goals.iter().map(|s| s.1 as u64).collect()),
+        );
+        df.write_parquet(
+            &format!("{}-goal.parquet", prefix),
+            CompressionOptions::Uncompressed,
+        )?;
+
+        let blocked = self.states_where(|c| matches!(c, GridCell::Wall | GridCell::Pit));
+        let mut df = DataFrame::new(vec![]);
+        df.push(
+            "terminal_x",
+            Series::new(blocked.iter().map(|s| s.0 as u64).collect()),
+        );
+        df.push(
+            "terminal_y",
+            Series::new(blocked.iter().map(|s| s.1 as u64).collect()),
+        );
+        df.write_parquet(
+            &format!("{}-terminal.parquet", prefix),
+            CompressionOptions::Uncompressed,
+        )
+    }
+
+    fn index(&self, &(x, y): &(usize, usize)) -> usize {
+        assert!(x < self.num_x && y < self.num_y, "Cell out of the grid");
+        y * self.num_x + x
+    }
+
+    fn states_where(&self, pred: impl Fn(&GridCell) -> bool) -> Vec<(usize, usize)> {
+        (0..self.num_x)
+            .flat_map(|x| (0..self.num_y).map(move |y| (x, y)))
+            .filter(|s| pred(&self.get_cell(s)))
+            .collect()
+    }
+
+    /// Cell reached by `action` from `state`, `None` if it lies off the grid
+    fn target(&self, state: &(usize, usize), action: &GridWorldAction) -> Option<(usize, usize)> {
+        let &(x, y) = state;
+        match action {
+            GWA::Up => (y + 1 < self.num_y).then_some((x, y + 1)),
+            GWA::Down => y.checked_sub(1).map(|y| (x, y)),
+            GWA::Left => x.checked_sub(1).map(|x| (x, y)),
+            GWA::Right => (x + 1 < self.num_x).then_some((x + 1, y)),
+        }
+    }
+
+    /// Where a move in `direction` lands, before the wind; `done` only after leaving the grid
+    fn land(&self, state: &(usize, usize), direction: &GridWorldAction) -> Landing {
+        match self.target(state, direction) {
+            None => Landing {
+                cell: *state,
+                bumped: true,
+                done: self.boundary == BoundaryBehavior::Terminate,
+            },
+            Some(target) => match self.get_cell(&target) {
+                GridCell::Wall => Landing {
+                    cell: *state,
+                    bumped: true,
+                    done: false,
+                },
+                _ => Landing {
+                    cell: target,
+                    bumped: false,
+                    done: false,
+                },
+            },
+        }
+    }
+
+    /// Push `landing` up to `strength` cells; walls and the top edge stop the wind
+    ///
+    /// Only the final cell counts, so the wind can carry the agent past a goal or a pit.
+    fn blow(&self, mut landing: Landing, strength: usize) -> Landing {
+        if landing.done {
+            return landing;
+        }
+        for _ in 0..strength {
+            match self.target(&landing.cell, &GWA::Up) {
+                Some(target) if self.get_cell(&target) != GridCell::Wall => landing.cell = target,
+                _ => break,
             }
         }
-        states
+        landing.done = self.get_cell(&landing.cell).is_terminal();
+        landing
+    }
+
+    /// `(next_state, reward)` of moving in `direction` under a wind of `strength`;
+    /// `None` marks termination, a restarting pit leads to the initial state
+    fn resolve(
+        &self,
+        state: &(usize, usize),
+        action: &GridWorldAction,
+        direction: &GridWorldAction,
+        strength: usize,
+    ) -> (Option<(usize, usize)>, f64) {
+        let landing = self.blow(self.land(state, direction), strength);
+        let cell = self.get_cell(&landing.cell);
+        let reward = self
+            .rewards
+            .reward(state, action, &landing.cell, cell, landing.bumped);
+        let next_state = match (landing.done, cell, self.pits) {
+            (true, GridCell::Pit, PitBehavior::Restart) => Some(self.init_state),
+            (true, _, _) => None,
+            (false, _, _) => Some(landing.cell),
+        };
+        (next_state, reward)
+    }
+
+    /// Nominal `(next_state, reward)`: the intended move under the given wind
+    fn outcome(
+        &self,
+        state: &(usize, usize),
+        action: &GridWorldAction,
+    ) -> (Option<(usize, usize)>, f64) {
+        if self.get_cell(state) != GridCell::Empty {
+            return (None, 0.0);
+        }
+        self.resolve(state, action, action, self.wind[state.0])
+    }
+}
+
+/// End of a single move: the cell reached (the start cell after a bump) and whether the
+/// episode ends there
+#[derive(Debug, Clone, Copy)]
+struct Landing {
+    cell: (usize, usize),
+    bumped: bool,
+    done: bool,
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Reward Specification
+// └──────────────────────────────────────────────────────────┘
+/// Reward term depending on `(s, a, s')`
+pub type TransitionReward =
+    Arc<dyn Fn(&(usize, usize), &GridWorldAction, &(usize, usize)) -> f64 + Send + Sync>;
+
+/// Rewards of a grid world
+///
+/// Every step falls in exactly one case, whose reward is
+/// - a bump (wall or grid edge): `bump`
+/// - entering a pit: `pit`
+/// - entering a goal: `goal`
+/// - entering an empty cell: `step`, the per-step cost
+///
+/// A per-cell reward replaces the case reward when that cell is entered, so goals can have
+/// different payoffs. A pit or goal reached by the wind after a bump still pays as entered.
+/// The optional transition reward is added on top; its `s'` is the cell the step ends in,
+/// even if the episode ends there, which is `s` itself after a plain bump.
+#[derive(Clone)]
+pub struct RewardSpec {
+    step: f64,
+    bump: f64,
+    pit: f64,
+    goal: f64,
+    cell_rewards: HashMap<(usize, usize), f64>,
+    transition_reward: Option<TransitionReward>,
+}
+
+impl RewardSpec {
+    pub fn new(step: f64, bump: f64, pit: f64, goal: f64) -> Self {
+        RewardSpec {
+            step,
+            bump,
+            pit,
+            goal,
+            cell_rewards: HashMap::new(),
+            transition_reward: None,
+        }
+    }
+
+    pub fn get_step_reward(&self) -> f64 {
+        self.step
+    }
+
+    pub fn get_bump_reward(&self) -> f64 {
+        self.bump
+    }
+
+    pub fn get_pit_reward(&self) -> f64 {
+        self.pit
+    }
+
+    pub fn get_goal_reward(&self) -> f64 {
+        self.goal
+    }
+
+    pub fn get_cell_reward(&self, cell: &(usize, usize)) -> Option<f64> {
+        self.cell_rewards.get(cell).copied()
+    }
+
+    pub fn get_cell_rewards(&self) -> &HashMap<(usize, usize), f64> {
+        &self.cell_rewards
+    }
+
+    /// Reward for entering `cell`, replacing the step, pit or goal reward there
+    pub fn set_cell_reward(&mut self, cell: (usize, usize), reward: f64) {
+        self.cell_rewards.insert(cell, reward);
+    }
+
+    pub fn set_transition_reward(
+        &mut self,
+        f: impl Fn(&(usize, usize), &GridWorldAction, &(usize, usize)) -> f64 + Send + Sync + 'static,
+    ) {
+        self.transition_reward = Some(Arc::new(f));
+    }
+
+    /// Reward of the step `state --action--> entered`, where `entered` is a cell of kind `cell`
+    fn reward(
+        &self,
+        state: &(usize, usize),
+        action: &GridWorldAction,
+        entered: &(usize, usize),
+        cell: GridCell,
+        bumped: bool,
+    ) -> f64 {
+        let base = match cell {
+            GridCell::Pit => self.get_cell_reward(entered).unwrap_or(self.pit),
+            GridCell::Goal => self.get_cell_reward(entered).unwrap_or(self.goal),
+            _ if bumped => self.bump,
+            _ => self.get_cell_reward(entered).unwrap_or(self.step),
+        };
+        base + self
+            .transition_reward
+            .as_ref()
+            .map_or(0.0, |f| f(state, action, entered))
+    }
+}
+
+impl Default for RewardSpec {
+    /// +1 for a goal, -1 for a pit or a bump, 0 per step
+    fn default() -> Self {
+        RewardSpec::new(0.0, -1.0, -1.0, 1.0)
+    }
+}
+
+impl fmt::Debug for RewardSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RewardSpec")
+            .field("step", &self.step)
+            .field("bump", &self.bump)
+            .field("pit", &self.pit)
+            .field("goal", &self.goal)
+            .field("cell_rewards", &self.cell_rewards)
+            .field("transition_reward", &self.transition_reward.is_some())
+            .finish()
+    }
+}
+
+impl MarkovDecisionProcess<(usize, usize), GridWorldAction> for GridWorld {
+    /// Empty cells only
+    fn states(&self) -> Vec<(usize, usize)> {
+        self.find_cells(GridCell::Empty)
     }
 
     fn actions(&self) -> Vec<GridWorldAction> {
         vec![GWA::Up, GWA::Down, GWA::Left, GWA::Right]
     }
 
+    /// Every move is available in an empty cell (bumps included); none elsewhere
     fn actions_at(&self, state: &(usize, usize)) -> Vec<GridWorldAction> {
-        let x_max = self.num_x - 1;
-        let y_max = self.num_y - 1;
-
-        match state {
-            (0, 0) => vec![GWA::Right, GWA::Up],
-            (0, y) if *y == y_max => vec![GWA::Right, GWA::Down],
-            (0, _) => vec![GWA::Right, GWA::Up, GWA::Down],
-            (x, 0) if *x == x_max => vec![GWA::Left, GWA::Up],
-            (x, y) if *x == x_max && *y == y_max => vec![GWA::Left, GWA::Down],
-            (x, _) if *x == x_max => vec![GWA::Left, GWA::Up, GWA::Down],
-            (_, 0) => vec![GWA::Left, GWA::Right, GWA::Up],
-            (_, y) if *y == y_max => vec![GWA::Left, GWA::Right, GWA::Down],
-            (_, _) => vec![GWA::Left, GWA::Right, GWA::Up, GWA::Down],
+        if self.get_cell(state) == GridCell::Empty {
+            self.actions()
+        } else {
+            vec![]
         }
     }
 
     fn reward(&self, state: &(usize, usize), action: &GridWorldAction) -> f64 {
-        if let Some(next_state) = self.transition(state, action) {
-            if next_state == self.goal_state {
-                1.0
-            } else {
-                0.0
-            }
-        } else {
-            -1.0
-        }
+        self.outcome(state, action).1
     }
 
     fn transition(
@@ -104,44 +621,277 @@ impl MarkovDecisionProcess<(usize, usize), GridWorldAction> for GridWorld {
         state: &(usize, usize),
         action: &GridWorldAction,
     ) -> Option<(usize, usize)> {
-        if self.terminal_states.contains(state) || self.goal_state.eq(state) {
-            return None;
-        }
+        self.outcome(state, action).0
+    }
 
-        let &(x, y) = state;
+    fn step(
+        &self,
+        state: &(usize, usize),
+        action: &GridWorldAction,
+    ) -> (Option<(usize, usize)>, f64) {
+        self.outcome(state, action)
+    }
 
-        let x_max = self.num_x - 1;
-        let y_max = self.num_y - 1;
+    /// Exact over slips and wind gusts; equal outcomes are merged
+    fn transition_probs(
+        &self,
+        state: &(usize, usize),
+        action: &GridWorldAction,
+    ) -> Vec<(Option<(usize, usize)>, f64, f64)> {
+        if self.get_cell(state) != GridCell::Empty {
+            return vec![(None, 1.0, 0.0)];
+        }
+        let [side_a, side_b] = action.perpendicular();
+        let directions = [
+            (*action, 1.0 - self.slip),
+            (side_a, self.slip / 2.0),
+            (side_b, self.slip / 2.0),
+        ];
+        let strength = self.wind[state.0];
+        let strengths = match self.wind_mode {
+            WindMode::Stochastic if strength > 0 => vec![
+                (strength - 1, 1.0 / 3.0),
+                (strength, 1.0 / 3.0),
+                (strength + 1, 1.0 / 3.0),
+            ],
+            _ => vec![(strength, 1.0)],
+        };
 
-        match action {
-            GWA::Up => {
-                if y == y_max {
-                    None
-                } else {
-                    Some((x, y + 1))
+        let mut outcomes = Vec::new();
+        for (direction, p_direction) in directions.iter().filter(|(_, p)| *p > 0.0) {
+            for (strength, p_strength) in strengths.iter() {
+                let (next_state, reward) = self.resolve(state, action, direction, *strength);
+                let p = p_direction * p_strength;
+                match outcomes
+                    .iter_mut()
+                    .find(|(s, _, r)| *s == next_state && *r == reward)
+                {
+                    Some(outcome) => outcome.1 += p,
+                    None => outcomes.push((next_state, p, reward)),
                 }
             }
-            GWA::Down => {
-                if y == 0 {
-                    None
-                } else {
-                    Some((x, y - 1))
-                }
-            }
-            GWA::Left => {
-                if x == 0 {
-                    None
-                } else {
-                    Some((x - 1, y))
-                }
-            }
-            GWA::Right => {
-                if x == x_max {
-                    None
-                } else {
-                    Some((x + 1, y))
-                }
+        }
+        outcomes
+    }
+}
+
+impl EpisodicProcess<(usize, usize), GridWorldAction> for GridWorld {
+    fn init_state(&self, _rng: &mut dyn RngCore) -> (usize, usize) {
+        self.init_state
+    }
+}
+
+impl ExploringStarts<(usize, usize), GridWorldAction> for GridWorld {
+    /// Uniform over empty cells, then uniform over the actions
+    fn exploring_start(&self, rng: &mut dyn RngCore) -> ((usize, usize), GridWorldAction) {
+        let state = self
+            .states()
+            .into_iter()
+            .choose(rng)
+            .expect("No non-terminal state");
+        let action = self.actions_at(&state).into_iter().choose(rng).unwrap();
+        (state, action)
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Parquet Output
+// └──────────────────────────────────────────────────────────┘
+/// Write an episode as `episode_x`, `episode_y`, `reward` columns
+pub fn write_episode_parquet<A>(
+    episode: &[((usize, usize), A, f64)],
+    file_path: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new(vec![]);
+    df.push(
+        "episode_x",
+        Series::new(episode.iter().map(|((x, _), _, _)| *x as u64).collect()),
+    );
+    df.push(
+        "episode_y",
+        Series::new(episode.iter().map(|((_, y), _, _)| *y as u64).collect()),
+    );
+    df.push(
+        "reward",
+        Series::new(episode.iter().map(|(_, _, r)| *r).collect::<Vec<f64>>()),
+    );
+    df.write_parquet(file_path, CompressionOptions::Uncompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// S . X
+    /// . # G
+    fn small_map() -> GridWorld {
+        GridWorld::from_map(&"S.X\n.#G\n".parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn walls_block_and_terminal_cells_end_the_episode() {
+        let env = small_map();
+        assert_eq!(env.get_init_state(), (0, 1));
+        assert_eq!(env.get_walls(), vec![(1, 0)]);
+        assert_eq!(env.states(), vec![(0, 0), (0, 1), (1, 1)]);
+
+        // Bump into the wall
+        assert_eq!(env.step(&(0, 0), &GWA::Right), (Some((0, 0)), -1.0));
+        // Pit and goal
+        assert_eq!(env.step(&(1, 1), &GWA::Right), (None, -1.0));
+        assert_eq!(env.step(&(1, 1), &GWA::Down), (Some((1, 1)), -1.0));
+        assert_eq!(env.step(&(0, 0), &GWA::Up), (Some((0, 1)), 0.0));
+        assert!(env.actions_at(&(2, 0)).is_empty());
+    }
+
+    #[test]
+    fn boundary_behavior() {
+        let mut env = small_map();
+        assert_eq!(env.step(&(0, 1), &GWA::Up), (Some((0, 1)), -1.0));
+        env.set_boundary(BoundaryBehavior::Terminate);
+        assert_eq!(env.step(&(0, 1), &GWA::Up), (None, -1.0));
+    }
+
+    #[test]
+    fn map_round_trip() {
+        let env = small_map();
+        assert_eq!(env.to_map().to_string(), "S.X\n.#G\n");
+        let env = GridWorld::with_walls(3, 2, (0, 0), (2, 1), vec![(1, 1)]);
+        assert_eq!(env.to_map().to_string(), ".#G\nS..\n");
+    }
+
+    #[test]
+    fn reward_spec_precedence() {
+        let mut env = small_map();
+        let mut rewards = RewardSpec::new(-0.1, -2.0, -5.0, 10.0);
+        rewards.set_cell_reward((0, 0), 7.0);
+        rewards.set_cell_reward((2, 0), 3.0);
+        env.set_reward_spec(rewards.clone());
+
+        // Cell rewards replace the step and goal rewards, but not a bump out of that cell
+        assert_eq!(env.reward(&(0, 1), &GWA::Down), 7.0);
+        assert_eq!(env.reward(&(0, 0), &GWA::Down), -2.0);
+        assert_eq!(env.reward(&(0, 1), &GWA::Right), -0.1);
+        assert_eq!(env.reward(&(1, 1), &GWA::Right), -5.0);
+        let mut goal_env = GridWorld::with_walls(2, 1, (0, 0), (1, 0), vec![]);
+        goal_env.set_reward_spec(rewards.clone());
+        assert_eq!(goal_env.reward(&(0, 0), &GWA::Right), 10.0);
+
+        // The transition reward sees s' = s after a bump and is added to every case
+        rewards.set_transition_reward(|s, _, s_next| if s == s_next { 0.5 } else { 1.0 });
+        env.set_reward_spec(rewards);
+        assert_eq!(env.reward(&(0, 0), &GWA::Left), -1.5);
+        assert_eq!(env.reward(&(0, 1), &GWA::Down), 8.0);
+        assert_eq!(env.reward(&(1, 1), &GWA::Right), -4.0);
+    }
+
+    #[test]
+    fn map_digits_are_entry_rewards() {
+        let env = GridWorld::from_map(&"S4G\n".parse().unwrap()).unwrap();
+        assert_eq!(env.step(&(0, 0), &GWA::Right), (Some((1, 0)), 4.0));
+        assert_eq!(env.step(&(1, 0), &GWA::Right), (None, 1.0));
+        assert_eq!(env.to_map().to_string(), "S4G\n");
+    }
+
+    type Outcome = (Option<(usize, usize)>, f64, f64);
+
+    fn assert_outcomes(actual: Vec<Outcome>, expected: &[Outcome]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((s, p, r), (s_exp, p_exp, r_exp)) in actual.iter().zip(expected) {
+            assert_eq!((s, r), (s_exp, r_exp));
+            assert!((p - p_exp).abs() < 1e-12, "{:?}", actual);
+        }
+    }
+
+    fn assert_distributions(env: &GridWorld) {
+        for s in env.states() {
+            for a in env.actions_at(&s) {
+                let total: f64 = env.transition_probs(&s, &a).iter().map(|(_, p, _)| p).sum();
+                assert!((total - 1.0).abs() < 1e-12, "{:?} {:?}: {}", s, a, total);
             }
         }
     }
+
+    #[test]
+    fn slippery_transition_probs() {
+        let mut env = GridWorld::with_walls(3, 3, (1, 1), (2, 2), vec![]);
+        env.set_slip(0.2);
+        assert_outcomes(
+            env.transition_probs(&(1, 1), &GWA::Up),
+            &[
+                (Some((1, 2)), 0.8, 0.0),
+                (Some((0, 1)), 0.1, 0.0),
+                (Some((2, 1)), 0.1, 0.0),
+            ],
+        );
+        // Bumps down and to the left merge
+        assert_outcomes(
+            env.transition_probs(&(0, 0), &GWA::Down),
+            &[(Some((0, 0)), 0.9, -1.0), (Some((1, 0)), 0.1, 0.0)],
+        );
+        assert_distributions(&env);
+        assert_distributions(&GridWorld::frozen_lake());
+    }
+
+    #[test]
+    fn windy_transition_probs() {
+        let third = 1.0 / 3.0;
+        let env = GridWorld::windy(WindMode::Steady);
+        assert_outcomes(
+            env.transition_probs(&(7, 1), &GWA::Up),
+            &[(Some((7, 4)), 1.0, -1.0)],
+        );
+        // Wind of the starting column blows through the goal
+        assert_outcomes(
+            env.transition_probs(&(6, 3), &GWA::Right),
+            &[(Some((7, 5)), 1.0, -1.0)],
+        );
+
+        let env = GridWorld::windy(WindMode::Stochastic);
+        assert_outcomes(
+            env.transition_probs(&(7, 1), &GWA::Up),
+            &[
+                (None, third, -1.0),
+                (Some((7, 4)), third, -1.0),
+                (Some((7, 5)), third, -1.0),
+            ],
+        );
+        // The top edge merges the two stronger gusts
+        assert_outcomes(
+            env.transition_probs(&(4, 5), &GWA::Up),
+            &[(Some((4, 6)), 1.0, -1.0)],
+        );
+        assert_outcomes(
+            env.transition_probs(&(4, 4), &GWA::Left),
+            &[
+                (Some((3, 4)), third, -1.0),
+                (Some((3, 5)), third, -1.0),
+                (Some((3, 6)), third, -1.0),
+            ],
+        );
+        // Calm columns stay deterministic
+        assert_outcomes(
+            env.transition_probs(&(0, 3), &GWA::Right),
+            &[(Some((1, 3)), 1.0, -1.0)],
+        );
+        assert_eq!(env.step(&(7, 1), &GWA::Up), (Some((7, 4)), -1.0));
+        assert_distributions(&env);
+    }
+
+    #[test]
+    fn cliff_restarts_from_the_start() {
+        let mut env = GridWorld::cliff_walking();
+        assert_eq!(env.get_pits().len(), 10);
+        assert_eq!(env.step(&(0, 0), &GWA::Right), (Some((0, 0)), -100.0));
+        assert_eq!(env.step(&(5, 1), &GWA::Down), (Some((0, 0)), -100.0));
+        assert_eq!(env.step(&(11, 1), &GWA::Down), (None, -1.0));
+        assert_eq!(env.step(&(0, 0), &GWA::Left), (Some((0, 0)), -1.0));
+        assert_eq!(
+            env.transition_probs(&(5, 1), &GWA::Down),
+            vec![(Some((0, 0)), 1.0, -100.0)]
+        );
+
+        env.set_pit_behavior(PitBehavior::Terminate);
+        assert_eq!(env.step(&(5, 1), &GWA::Down), (None, -100.0));
+    }
 }
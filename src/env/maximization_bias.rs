@@ -0,0 +1,166 @@
+use crate::base::process::{EpisodicProcess, MarkovDecisionProcess};
+use peroxide::fuga::*;
+use MaximizationBiasAction as MBA;
+use MaximizationBiasState as MBS;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Maximization Bias MDP
+// └──────────────────────────────────────────────────────────┘
+/// Two-state MDP of Sutton & Barto (Example 6.7)
+///
+/// Episodes start in A. `Right` terminates with reward 0; `Left` moves to B with reward 0.
+/// Every action in B terminates with reward drawn from N(mean, std), so going left is worse
+/// on average (mean < 0) but looks attractive to a learner that maximizes over noisy estimates.
+#[derive(Debug, Clone)]
+pub struct MaximizationBias {
+    num_b_actions: usize,
+    mean: f64,
+    std: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MaximizationBiasState {
+    A,
+    B,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaximizationBiasAction {
+    Left,
+    Right,
+    /// i-th terminating action in B
+    Exit(usize),
+}
+
+impl MaximizationBias {
+    pub fn new(num_b_actions: usize, mean: f64, std: f64) -> Self {
+        assert!(num_b_actions >= 1, "B needs at least one action");
+        MaximizationBias {
+            num_b_actions,
+            mean,
+            std,
+        }
+    }
+
+    pub fn get_num_b_actions(&self) -> usize {
+        self.num_b_actions
+    }
+
+    pub fn get_mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn get_std(&self) -> f64 {
+        self.std
+    }
+}
+
+impl Default for MaximizationBias {
+    /// 10 actions in B with rewards N(-0.1, 1)
+    fn default() -> Self {
+        MaximizationBias::new(10, -0.1, 1.0)
+    }
+}
+
+impl MarkovDecisionProcess<MaximizationBiasState, MaximizationBiasAction> for MaximizationBias {
+    fn states(&self) -> Vec<MaximizationBiasState> {
+        vec![MBS::A, MBS::B]
+    }
+
+    fn actions(&self) -> Vec<MaximizationBiasAction> {
+        let mut actions = vec![MBA::Left, MBA::Right];
+        actions.extend((0..self.num_b_actions).map(MBA::Exit));
+        actions
+    }
+
+    fn actions_at(&self, state: &MaximizationBiasState) -> Vec<MaximizationBiasAction> {
+        match state {
+            MBS::A => vec![MBA::Left, MBA::Right],
+            MBS::B => (0..self.num_b_actions).map(MBA::Exit).collect(),
+        }
+    }
+
+    /// Expected reward (the mean of the normal distribution in B)
+    fn reward(&self, state: &MaximizationBiasState, _action: &MaximizationBiasAction) -> f64 {
+        match state {
+            MBS::A => 0.0,
+            MBS::B => self.mean,
+        }
+    }
+
+    fn transition(
+        &self,
+        state: &MaximizationBiasState,
+        action: &MaximizationBiasAction,
+    ) -> Option<MaximizationBiasState> {
+        match (state, action) {
+            (MBS::A, MBA::Left) => Some(MBS::B),
+            _ => None,
+        }
+    }
+
+    /// Rewards in B are sampled from N(mean, std) (Box-Muller on `rng`)
+    fn sample_step(
+        &self,
+        state: &MaximizationBiasState,
+        action: &MaximizationBiasAction,
+        rng: &mut dyn RngCore,
+    ) -> (Option<MaximizationBiasState>, f64) {
+        let next_state = self.transition(state, action);
+        let reward = match state {
+            MBS::A => 0.0,
+            MBS::B => {
+                let u1 = 1f64 - rng.gen::<f64>();
+                let u2 = rng.gen::<f64>();
+                let z = (-2f64 * u1.ln()).sqrt() * (2f64 * std::f64::consts::PI * u2).cos();
+                self.mean + self.std * z
+            }
+        };
+        (next_state, reward)
+    }
+}
+
+impl EpisodicProcess<MaximizationBiasState, MaximizationBiasAction> for MaximizationBias {
+    fn init_state(&self, _rng: &mut dyn RngCore) -> MaximizationBiasState {
+        MBS::A
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn right_from_a_terminates_with_zero_reward() {
+        let env = MaximizationBias::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            assert_eq!(env.sample_step(&MBS::A, &MBA::Right, &mut rng), (None, 0.0));
+            assert_eq!(
+                env.sample_step(&MBS::A, &MBA::Left, &mut rng),
+                (Some(MBS::B), 0.0)
+            );
+        }
+    }
+
+    #[test]
+    fn b_rewards_are_normal_around_the_mean() {
+        let env = MaximizationBias::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 20000;
+        let rewards: Vec<f64> = (0..n)
+            .map(|i| {
+                let (next_state, reward) = env.sample_step(&MBS::B, &MBA::Exit(i % 10), &mut rng);
+                assert_eq!(next_state, None);
+                reward
+            })
+            .collect();
+        let mean = rewards.iter().sum::<f64>() / n as f64;
+        let var = rewards.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+
+        // Standard error of the mean is 1 / √20000 ≈ 0.007
+        assert!((mean - -0.1).abs() < 0.03, "mean = {}", mean);
+        assert!((var.sqrt() - 1.0).abs() < 0.03, "std = {}", var.sqrt());
+        assert_eq!(env.reward(&MBS::B, &MBA::Exit(0)), -0.1);
+    }
+}
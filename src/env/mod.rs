@@ -1 +1,7 @@
+pub mod acrobot;
+pub mod cart_pole;
+mod dynamics;
+pub mod grid_map;
 pub mod grid_world;
+pub mod maximization_bias;
+pub mod mountain_car;
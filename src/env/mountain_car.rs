@@ -0,0 +1,170 @@
+use super::dynamics::{integrate, ControlInput};
+use crate::base::features::StateRange;
+use crate::base::process::{EpisodicProcess, MarkovDecisionProcess};
+use peroxide::fuga::*;
+use MountainCarAction as MCA;
+
+const MIN_POSITION: f64 = -1.2;
+const MAX_POSITION: f64 = 0.5;
+const MAX_SPEED: f64 = 0.07;
+const FORCE: f64 = 0.001;
+const GRAVITY: f64 = 0.0025;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Mountain Car
+// └──────────────────────────────────────────────────────────┘
+/// Mountain Car of Sutton & Barto (Example 10.1)
+///
+/// State `[position, velocity]`. The continuous-time dynamics ẋ = v, v̇ = 0.001 a - 0.0025 cos(3x)
+/// are integrated over one time unit per step with `method`; afterwards the velocity is clipped
+/// to ±0.07 and the position to [-1.2, 0.5], with the velocity zeroed at the left wall.
+/// Every step costs -1 and reaching position 0.5 terminates. Episodes start at rest with the
+/// position uniform in [-0.6, -0.4].
+#[derive(Debug, Clone)]
+pub struct MountainCar {
+    method: ExMethod,
+    substeps: usize,
+    time_limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MountainCarAction {
+    Reverse,
+    Coast,
+    Forward,
+}
+
+impl MountainCarAction {
+    /// Throttle a ∈ {-1, 0, 1}
+    pub fn throttle(&self) -> f64 {
+        match self {
+            MCA::Reverse => -1f64,
+            MCA::Coast => 0f64,
+            MCA::Forward => 1f64,
+        }
+    }
+}
+
+impl MountainCar {
+    pub fn new(method: ExMethod, substeps: usize, time_limit: Option<usize>) -> Self {
+        assert!(substeps >= 1, "substeps must be at least 1");
+        MountainCar {
+            method,
+            substeps,
+            time_limit,
+        }
+    }
+
+    pub fn get_method(&self) -> ExMethod {
+        self.method
+    }
+
+    pub fn get_substeps(&self) -> usize {
+        self.substeps
+    }
+
+    pub fn get_time_limit(&self) -> Option<usize> {
+        self.time_limit
+    }
+
+    /// Position and velocity bounds
+    pub fn state_range(&self) -> StateRange {
+        StateRange::new(
+            vec![MIN_POSITION, -MAX_SPEED],
+            vec![MAX_POSITION, MAX_SPEED],
+        )
+    }
+
+    fn next_state(&self, state: &[f64; 2], action: &MountainCarAction) -> [f64; 2] {
+        let input = ControlInput {
+            u: action.throttle(),
+        };
+        let y = integrate(dynamics, input, state, 1f64, self.substeps, self.method);
+        let v = y[1].clamp(-MAX_SPEED, MAX_SPEED);
+        let x = y[0].clamp(MIN_POSITION, MAX_POSITION);
+        let v = if x == MIN_POSITION && v < 0f64 {
+            0f64
+        } else {
+            v
+        };
+        [x, v]
+    }
+}
+
+impl Default for MountainCar {
+    /// RK4 with one substep and a 200-step time limit
+    fn default() -> Self {
+        MountainCar::new(ExMethod::RK4, 1, Some(200))
+    }
+}
+
+fn dynamics(st: &mut State<f64>, input: &ControlInput) {
+    let x = &st.value;
+    st.deriv = vec![x[1], FORCE * input.u - GRAVITY * (3f64 * x[0]).cos()];
+}
+
+impl MarkovDecisionProcess<[f64; 2], MountainCarAction> for MountainCar {
+    /// Continuous state space: there is nothing to enumerate
+    fn states(&self) -> Vec<[f64; 2]> {
+        vec![]
+    }
+
+    fn actions(&self) -> Vec<MountainCarAction> {
+        vec![MCA::Reverse, MCA::Coast, MCA::Forward]
+    }
+
+    fn actions_at(&self, _state: &[f64; 2]) -> Vec<MountainCarAction> {
+        self.actions()
+    }
+
+    fn reward(&self, _state: &[f64; 2], _action: &MountainCarAction) -> f64 {
+        -1f64
+    }
+
+    fn transition(&self, state: &[f64; 2], action: &MountainCarAction) -> Option<[f64; 2]> {
+        let next_state = self.next_state(state, action);
+        if next_state[0] >= MAX_POSITION {
+            None
+        } else {
+            Some(next_state)
+        }
+    }
+}
+
+impl EpisodicProcess<[f64; 2], MountainCarAction> for MountainCar {
+    fn init_state(&self, rng: &mut dyn RngCore) -> [f64; 2] {
+        [rng.gen_range(-0.6..-0.4), 0f64]
+    }
+
+    fn time_limit(&self) -> Option<usize> {
+        self.time_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn velocity_is_clipped() {
+        let env = MountainCar::default();
+        let next = env.transition(&[-0.5, MAX_SPEED], &MCA::Forward).unwrap();
+        assert_eq!(next[1], MAX_SPEED);
+        let next = env.transition(&[0.0, -MAX_SPEED], &MCA::Reverse).unwrap();
+        assert_eq!(next[1], -MAX_SPEED);
+    }
+
+    #[test]
+    fn left_wall_stops_the_car() {
+        let env = MountainCar::default();
+        let next = env.transition(&[-1.19, -MAX_SPEED], &MCA::Reverse);
+        assert_eq!(next, Some([MIN_POSITION, 0.0]));
+    }
+
+    #[test]
+    fn reaching_the_goal_is_terminal() {
+        let env = MountainCar::default();
+        assert_eq!(env.step(&[0.45, MAX_SPEED], &MCA::Forward), (None, -1.0));
+        assert!(env.transition(&[0.3, 0.0], &MCA::Forward).is_some());
+    }
+}
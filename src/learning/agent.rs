@@ -1,4 +1,170 @@
-use crate::base::policy::Policy;
+use super::value_prediction::{OffPolicyMC, TransitionPredictor, ValuePredictor};
+pub use crate::base::agent::Agent;
+use crate::base::policy::{EpsilonGreedyValuePolicy, Policy};
 use crate::base::process::MarkovDecisionProcess;
+use peroxide::fuga::*;
 
-pub trait Agent {}
+// ┌──────────────────────────────────────────────────────────┐
+//  Value Prediction Agent
+// └──────────────────────────────────────────────────────────┘
+/// State-value predictor paired with an ε-greedy policy over its value function
+///
+/// Actions are chosen on the predictor's live value function; the policy's own copy is
+/// refreshed once per episode, in `end_episode`.
+pub struct ValuePredictionAgent<'a, S, A, M, V>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone,
+    M: MarkovDecisionProcess<S, A>,
+    V: ValuePredictor<S>,
+{
+    policy: EpsilonGreedyValuePolicy<'a, S, A, M>,
+    predictor: V,
+}
+
+impl<'a, S, A, M, V> ValuePredictionAgent<'a, S, A, M, V>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone,
+    M: MarkovDecisionProcess<S, A>,
+    V: ValuePredictor<S>,
+{
+    pub fn new(policy: EpsilonGreedyValuePolicy<'a, S, A, M>, predictor: V) -> Self {
+        ValuePredictionAgent { policy, predictor }
+    }
+
+    pub fn get_policy(&self) -> &EpsilonGreedyValuePolicy<'a, S, A, M> {
+        &self.policy
+    }
+
+    pub fn get_predictor(&self) -> &V {
+        &self.predictor
+    }
+
+    fn sync_policy(&mut self) {
+        self.policy
+            .update_value_function(self.predictor.get_value_function());
+    }
+}
+
+impl<'a, S, A, M, V> Agent<S, A> for ValuePredictionAgent<'a, S, A, M, V>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone,
+    M: MarkovDecisionProcess<S, A>,
+    V: TransitionPredictor<S>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.policy
+            .gen_action_over(self.predictor.get_value_function(), state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        _action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.predictor.feed(state, reward, next_state);
+    }
+
+    fn begin_episode(&mut self) {
+        self.predictor.start_episode();
+    }
+
+    fn end_episode(&mut self) {
+        self.predictor.finish_episode();
+        self.sync_policy();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.policy.turn_off_random();
+        } else {
+            self.policy.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Off-policy Prediction Agent
+// └──────────────────────────────────────────────────────────┘
+/// Acts with the behavior policy and evaluates the target policy at episode end
+///
+/// In greedy mode the target policy is followed instead and nothing is learned.
+pub struct OffPolicyPredictionAgent<S, A, P, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    P: Policy<S, A>,
+    B: Policy<S, A>,
+{
+    predictor: OffPolicyMC<S, A, P, B>,
+    episode: Vec<(S, A, f64)>,
+    _greedy: bool,
+}
+
+impl<S, A, P, B> OffPolicyPredictionAgent<S, A, P, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    P: Policy<S, A>,
+    B: Policy<S, A>,
+{
+    pub fn new(predictor: OffPolicyMC<S, A, P, B>) -> Self {
+        OffPolicyPredictionAgent {
+            predictor,
+            episode: Vec::new(),
+            _greedy: false,
+        }
+    }
+
+    pub fn get_predictor(&self) -> &OffPolicyMC<S, A, P, B> {
+        &self.predictor
+    }
+}
+
+impl<S, A, P, B> Agent<S, A> for OffPolicyPredictionAgent<S, A, P, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    P: Policy<S, A>,
+    B: Policy<S, A>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        if self._greedy {
+            self.predictor.get_target_policy().gen_action(state, rng)
+        } else {
+            self.predictor.get_behavior_policy().gen_action(state, rng)
+        }
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        _next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.episode.push((state.clone(), action.clone(), reward));
+    }
+
+    fn begin_episode(&mut self) {
+        self.episode.clear();
+    }
+
+    fn end_episode(&mut self) {
+        if self.episode.is_empty() {
+            return;
+        }
+        self.predictor.update_episode(&self.episode);
+        self.predictor.step();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        self._greedy = greedy;
+    }
+}
@@ -0,0 +1,1855 @@
+use super::util::{ImportanceSampling, StepsizeScheduler};
+use super::value_prediction::EligibilityTrace;
+use crate::base::agent::Agent;
+use crate::base::function::QTable;
+use crate::base::policy::Policy;
+use peroxide::fuga::*;
+use std::collections::{HashMap, HashSet};
+
+pub trait ActionValuePredictor<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    fn get_q_table(&self) -> &QTable<S, A>;
+    fn step(&mut self);
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  SARSA
+// └──────────────────────────────────────────────────────────┘
+/// On-policy TD control
+///
+/// Q(S, A) <- Q(S, A) + α [R + γ Q(S', A') - Q(S, A)]
+#[allow(clippy::type_complexity)]
+pub struct SARSA<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    one_step: Option<(S, A, f64, Option<(S, A)>)>,
+    /// A' drawn in `observe`, paired with the S' it was drawn for
+    next_action: Option<(S, A)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> SARSA<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+    ) -> Self {
+        SARSA {
+            q_table,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            one_step: None,
+            next_action: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    /// Store (S, A, R, S', A') where `None` marks a terminal S'
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, sa_next: Option<(S, A)>) {
+        self.one_step = Some((s, a, r, sa_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for SARSA<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, sa_next) = self.one_step.take().unwrap();
+        let target = match sa_next {
+            Some((s_next, a_next)) => {
+                r + self.gamma * self.get_value(&s_next, &a_next).unwrap_or(0.0)
+            }
+            None => r,
+        };
+        let q = self.get_value(&s, &a).unwrap_or(0.0);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+        self.update_value(&sa.0, &sa.1, q + alpha * (target - q));
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for SARSA<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for SARSA<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        match self.next_action.take() {
+            Some((s, a)) if s == *state => Some(a),
+            _ => self.gen_action(state, rng),
+        }
+    }
+
+    /// A' is drawn here from the current policy and reused by the next `select_action` on S'
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let sa_next = next_state.and_then(|s| self.gen_action(s, rng).map(|a| (s.clone(), a)));
+        self.next_action = sa_next.clone();
+        self.update_one_step(state.clone(), action.clone(), reward, sa_next);
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.next_action = None;
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        self.next_action = None;
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Q-Learning
+// └──────────────────────────────────────────────────────────┘
+/// Off-policy TD control
+///
+/// Q(S, A) <- Q(S, A) + α [R + γ max_a Q(S', a) - Q(S, A)]
+pub struct QLearning<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    one_step: Option<(S, A, f64, Option<S>)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> QLearning<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+    ) -> Self {
+        QLearning {
+            q_table,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            one_step: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, a, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for QLearning<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, s_next) = self.one_step.take().unwrap();
+        let target = match s_next {
+            Some(s_next) => r + self.gamma * self.q_table.max_value(&s_next),
+            None => r,
+        };
+        let q = self.get_value(&s, &a).unwrap_or(0.0);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+        self.update_value(&sa.0, &sa.1, q + alpha * (target - q));
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for QLearning<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for QLearning<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.update_one_step(state.clone(), action.clone(), reward, next_state.cloned());
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Expected SARSA
+// └──────────────────────────────────────────────────────────┘
+/// TD control with the expectation over the ε-greedy policy
+///
+/// Q(S, A) <- Q(S, A) + α [R + γ Σ_a π(a|S') Q(S', a) - Q(S, A)]
+pub struct ExpectedSARSA<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    one_step: Option<(S, A, f64, Option<S>)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ExpectedSARSA<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+    ) -> Self {
+        ExpectedSARSA {
+            q_table,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            one_step: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, a, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    /// Σ_a π(a|s) Q(s, a) under the learning (ε-greedy) policy
+    pub fn expected_value(&self, state: &S) -> f64 {
+        self.q_table
+            .epsilon_greedy_probs(state, self.epsilon)
+            .iter()
+            .map(|(a, p)| p * self.get_value(state, a).unwrap_or(0.0))
+            .sum()
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for ExpectedSARSA<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, s_next) = self.one_step.take().unwrap();
+        let target = match s_next {
+            Some(s_next) => r + self.gamma * self.expected_value(&s_next),
+            None => r,
+        };
+        let q = self.get_value(&s, &a).unwrap_or(0.0);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+        self.update_value(&sa.0, &sa.1, q + alpha * (target - q));
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for ExpectedSARSA<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for ExpectedSARSA<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.update_one_step(state.clone(), action.clone(), reward, next_state.cloned());
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Double Q-Learning
+// └──────────────────────────────────────────────────────────┘
+/// Off-policy TD control with two decoupled estimates
+///
+/// With probability 1/2 (coin drawn in `observe`):
+/// Q1(S, A) <- Q1(S, A) + α [R + γ Q2(S', argmax_a Q1(S', a)) - Q1(S, A)]
+/// and symmetrically for Q2 otherwise. Ties in the argmax are averaged over.
+/// Actions are ε-greedy in Q1 + Q2; `get_q_table` returns (Q1 + Q2) / 2.
+#[allow(clippy::type_complexity)]
+pub struct DoubleQLearning<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_tables: [QTable<S, A>; 2],
+    q_table: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    one_step: Option<(S, A, f64, Option<S>, usize)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> DoubleQLearning<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+    ) -> Self {
+        DoubleQLearning {
+            q_tables: [q_table.clone(), q_table.clone()],
+            q_table,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            one_step: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    /// Q1 (`i = 0`) or Q2 (`i = 1`)
+    pub fn get_q_table_at(&self, i: usize) -> &QTable<S, A> {
+        &self.q_tables[i]
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    /// Set Q_i(s, a) and refresh the averaged table
+    pub fn update_value(&mut self, i: usize, s: &S, a: &A, value: f64) {
+        self.q_tables[i].update_value(s, a, value);
+        let other = self.q_tables[1 - i].get_value(s, a).unwrap_or(0.0);
+        self.q_table.update_value(s, a, (value + other) / 2f64);
+    }
+
+    /// Store (S, A, R, S') and the index of the table to update
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, s_next: Option<S>, i: usize) {
+        self.one_step = Some((s, a, r, s_next, i));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    /// Q_j(s, argmax_a Q_i(s, a)) with j = 1 - i, averaged over ties
+    pub fn double_max_value(&self, i: usize, state: &S) -> f64 {
+        let greedy = self.q_tables[i].greedy_actions(state);
+        if greedy.is_empty() {
+            return 0.0;
+        }
+        let other = &self.q_tables[1 - i];
+        greedy
+            .iter()
+            .map(|a| other.get_value(state, a).unwrap_or(0.0))
+            .sum::<f64>()
+            / greedy.len() as f64
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for DoubleQLearning<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, s_next, i) = self.one_step.take().unwrap();
+        let target = match s_next {
+            Some(s_next) => r + self.gamma * self.double_max_value(i, &s_next),
+            None => r,
+        };
+        let q = self.q_tables[i].get_value(&s, &a).unwrap_or(0.0);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+        self.update_value(i, &sa.0, &sa.1, q + alpha * (target - q));
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for DoubleQLearning<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for DoubleQLearning<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let i = if rng.gen::<f64>() < 0.5 { 0 } else { 1 };
+        self.update_one_step(
+            state.clone(),
+            action.clone(),
+            reward,
+            next_state.cloned(),
+            i,
+        );
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Double Expected SARSA
+// └──────────────────────────────────────────────────────────┘
+/// Expected SARSA with two decoupled estimates
+///
+/// With probability 1/2 (coin drawn in `observe`):
+/// Q1(S, A) <- Q1(S, A) + α [R + γ Σ_a π1(a|S') Q2(S', a) - Q1(S, A)]
+/// where π1 is ε-greedy in Q1, and symmetrically for Q2 otherwise.
+/// Actions are ε-greedy in Q1 + Q2; `get_q_table` returns (Q1 + Q2) / 2.
+#[allow(clippy::type_complexity)]
+pub struct DoubleExpectedSARSA<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_tables: [QTable<S, A>; 2],
+    q_table: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    one_step: Option<(S, A, f64, Option<S>, usize)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> DoubleExpectedSARSA<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+    ) -> Self {
+        DoubleExpectedSARSA {
+            q_tables: [q_table.clone(), q_table.clone()],
+            q_table,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            one_step: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    /// Q1 (`i = 0`) or Q2 (`i = 1`)
+    pub fn get_q_table_at(&self, i: usize) -> &QTable<S, A> {
+        &self.q_tables[i]
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    /// Set Q_i(s, a) and refresh the averaged table
+    pub fn update_value(&mut self, i: usize, s: &S, a: &A, value: f64) {
+        self.q_tables[i].update_value(s, a, value);
+        let other = self.q_tables[1 - i].get_value(s, a).unwrap_or(0.0);
+        self.q_table.update_value(s, a, (value + other) / 2f64);
+    }
+
+    /// Store (S, A, R, S') and the index of the table to update
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, s_next: Option<S>, i: usize) {
+        self.one_step = Some((s, a, r, s_next, i));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    /// Σ_a π_i(a|s) Q_j(s, a) with π_i ε-greedy in Q_i and j = 1 - i
+    pub fn double_expected_value(&self, i: usize, state: &S) -> f64 {
+        let other = &self.q_tables[1 - i];
+        self.q_tables[i]
+            .epsilon_greedy_probs(state, self.epsilon)
+            .iter()
+            .map(|(a, p)| p * other.get_value(state, a).unwrap_or(0.0))
+            .sum()
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for DoubleExpectedSARSA<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, s_next, i) = self.one_step.take().unwrap();
+        let target = match s_next {
+            Some(s_next) => r + self.gamma * self.double_expected_value(i, &s_next),
+            None => r,
+        };
+        let q = self.q_tables[i].get_value(&s, &a).unwrap_or(0.0);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+        self.update_value(i, &sa.0, &sa.1, q + alpha * (target - q));
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A>
+    for DoubleExpectedSARSA<S, A>
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A>
+    for DoubleExpectedSARSA<S, A>
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let i = if rng.gen::<f64>() < 0.5 { 0 } else { 1 };
+        self.update_one_step(
+            state.clone(),
+            action.clone(),
+            reward,
+            next_state.cloned(),
+            i,
+        );
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  SARSA(λ)
+// └──────────────────────────────────────────────────────────┘
+/// On-policy TD control with eligibility traces over (S, A)
+///
+/// δ = R + γ Q(S', A') - Q(S, A), then z(S, A) is bumped according to `trace_kind`,
+/// Q <- Q + α δ z and z <- γλ z. Traces are cleared on a terminal transition and at the
+/// start of every episode.
+#[allow(clippy::type_complexity)]
+pub struct SARSALambda<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    traces: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    lambda: f64,
+    epsilon: f64,
+    trace_kind: EligibilityTrace,
+    one_step: Option<(S, A, f64, Option<(S, A)>)>,
+    /// A' drawn in `observe`, paired with the S' it was drawn for
+    next_action: Option<(S, A)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> SARSALambda<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        lambda: f64,
+        epsilon: f64,
+        trace_kind: EligibilityTrace,
+    ) -> Self {
+        SARSALambda {
+            q_table,
+            traces: QTable::new(),
+            stepsize_scheduler,
+            gamma,
+            lambda,
+            epsilon,
+            trace_kind,
+            one_step: None,
+            next_action: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_trace(&self, s: &S, a: &A) -> f64 {
+        self.traces.get_value(s, a).unwrap_or(0.0)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    /// Store (S, A, R, S', A') where `None` marks a terminal S'
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, sa_next: Option<(S, A)>) {
+        self.one_step = Some((s, a, r, sa_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn reset_traces(&mut self) {
+        self.traces.clear();
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for SARSALambda<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, sa_next) = self.one_step.take().unwrap();
+        let q = self.get_value(&s, &a).unwrap_or(0.0);
+        let target = match &sa_next {
+            Some((s_next, a_next)) => {
+                r + self.gamma * self.get_value(s_next, a_next).unwrap_or(0.0)
+            }
+            None => r,
+        };
+        let delta = target - q;
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+
+        let z = self.get_trace(&sa.0, &sa.1);
+        let z = match self.trace_kind {
+            EligibilityTrace::Accumulating => z + 1f64,
+            EligibilityTrace::Replacing => 1f64,
+            EligibilityTrace::Dutch => (1f64 - alpha) * z + 1f64,
+        };
+        self.traces.update_value(&sa.0, &sa.1, z);
+
+        for (x, entries) in self.traces.get_table() {
+            for (b, z) in entries {
+                let q = self.q_table.get_value(x, b).unwrap_or(0.0);
+                self.q_table.update_value(x, b, q + alpha * delta * z);
+            }
+        }
+        self.traces.scale(self.gamma * self.lambda);
+        self.increment_count();
+
+        if sa_next.is_none() {
+            self.reset_traces();
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for SARSALambda<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for SARSALambda<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        match self.next_action.take() {
+            Some((s, a)) if s == *state => Some(a),
+            _ => self.gen_action(state, rng),
+        }
+    }
+
+    /// A' is drawn here from the current policy and reused by the next `select_action` on S'
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let sa_next = next_state.and_then(|s| self.gen_action(s, rng).map(|a| (s.clone(), a)));
+        self.next_action = sa_next.clone();
+        self.update_one_step(state.clone(), action.clone(), reward, sa_next);
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.next_action = None;
+        self.reset_traces();
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        self.next_action = None;
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Watkins's Q(λ)
+// └──────────────────────────────────────────────────────────┘
+/// Off-policy TD control with traces cut on exploratory actions
+///
+/// δ = R + γ max_a Q(S', a) - Q(S, A), z(S, A) is bumped according to `trace_kind` and
+/// Q <- Q + α δ z. If the next action A' is greedy z <- γλ z, otherwise z <- 0.
+#[allow(clippy::type_complexity)]
+pub struct WatkinsQLambda<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    traces: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    lambda: f64,
+    epsilon: f64,
+    trace_kind: EligibilityTrace,
+    one_step: Option<(S, A, f64, Option<(S, A)>)>,
+    /// A' drawn in `observe`, paired with the S' it was drawn for
+    next_action: Option<(S, A)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> WatkinsQLambda<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        lambda: f64,
+        epsilon: f64,
+        trace_kind: EligibilityTrace,
+    ) -> Self {
+        WatkinsQLambda {
+            q_table,
+            traces: QTable::new(),
+            stepsize_scheduler,
+            gamma,
+            lambda,
+            epsilon,
+            trace_kind,
+            one_step: None,
+            next_action: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_trace(&self, s: &S, a: &A) -> f64 {
+        self.traces.get_value(s, a).unwrap_or(0.0)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    /// Store (S, A, R, S', A') where `None` marks a terminal S'
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, sa_next: Option<(S, A)>) {
+        self.one_step = Some((s, a, r, sa_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn reset_traces(&mut self) {
+        self.traces.clear();
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for WatkinsQLambda<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        let (s, a, r, sa_next) = self.one_step.take().unwrap();
+        let q = self.get_value(&s, &a).unwrap_or(0.0);
+        let target = match &sa_next {
+            Some((s_next, _)) => r + self.gamma * self.q_table.max_value(s_next),
+            None => r,
+        };
+        let delta = target - q;
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+
+        let z = self.get_trace(&sa.0, &sa.1);
+        let z = match self.trace_kind {
+            EligibilityTrace::Accumulating => z + 1f64,
+            EligibilityTrace::Replacing => 1f64,
+            EligibilityTrace::Dutch => (1f64 - alpha) * z + 1f64,
+        };
+        self.traces.update_value(&sa.0, &sa.1, z);
+
+        // Greediness of A' is judged before this update, i.e. by the policy that chose it
+        let is_greedy = match &sa_next {
+            Some((s_next, a_next)) => self
+                .q_table
+                .greedy_actions(s_next)
+                .iter()
+                .any(|b| b == a_next),
+            None => false,
+        };
+
+        for (x, entries) in self.traces.get_table() {
+            for (b, z) in entries {
+                let q = self.q_table.get_value(x, b).unwrap_or(0.0);
+                self.q_table.update_value(x, b, q + alpha * delta * z);
+            }
+        }
+        if is_greedy {
+            self.traces.scale(self.gamma * self.lambda);
+        } else {
+            self.reset_traces();
+        }
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for WatkinsQLambda<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for WatkinsQLambda<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        match self.next_action.take() {
+            Some((s, a)) if s == *state => Some(a),
+            _ => self.gen_action(state, rng),
+        }
+    }
+
+    /// A' is drawn here so that the traces can be cut when it is exploratory
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let sa_next = next_state.and_then(|s| self.gen_action(s, rng).map(|a| (s.clone(), a)));
+        self.next_action = sa_next.clone();
+        self.update_one_step(state.clone(), action.clone(), reward, sa_next);
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.next_action = None;
+        self.reset_traces();
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        self.next_action = None;
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  True Online SARSA(λ)
+// └──────────────────────────────────────────────────────────┘
+/// True online SARSA(λ) (tabular case, dutch traces)
+///
+/// With x the indicator of (S, A) and Q_old the Q(S', A') of the previous step:
+/// z <- γλ z + (1 - αγλ z(S, A)) x
+/// Q <- Q + α (δ + Q(S, A) - Q_old) z - α (Q(S, A) - Q_old) x
+#[allow(clippy::type_complexity)]
+pub struct TrueOnlineSARSALambda<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    traces: QTable<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    lambda: f64,
+    epsilon: f64,
+    q_old: f64,
+    one_step: Option<(S, A, f64, Option<(S, A)>)>,
+    /// A' drawn in `observe`, paired with the S' it was drawn for
+    next_action: Option<(S, A)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> TrueOnlineSARSALambda<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        lambda: f64,
+        epsilon: f64,
+    ) -> Self {
+        TrueOnlineSARSALambda {
+            q_table,
+            traces: QTable::new(),
+            stepsize_scheduler,
+            gamma,
+            lambda,
+            epsilon,
+            q_old: 0f64,
+            one_step: None,
+            next_action: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_trace(&self, s: &S, a: &A) -> f64 {
+        self.traces.get_value(s, a).unwrap_or(0.0)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    /// Store (S, A, R, S', A') where `None` marks a terminal S'
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, sa_next: Option<(S, A)>) {
+        self.one_step = Some((s, a, r, sa_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn reset_traces(&mut self) {
+        self.traces.clear();
+        self.q_old = 0f64;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for TrueOnlineSARSALambda<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        let (s, a, r, sa_next) = self.one_step.take().unwrap();
+        let Q = self.get_value(&s, &a).unwrap_or(0.0);
+        let Q_next = sa_next
+            .as_ref()
+            .and_then(|(s_next, a_next)| self.get_value(s_next, a_next))
+            .unwrap_or(0.0);
+        let delta = r + self.gamma * Q_next - Q;
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+
+        // z <- γλ z + (1 - αγλ z(S, A)) x
+        let decay = self.gamma * self.lambda;
+        let z_sa = self.get_trace(&sa.0, &sa.1);
+        self.traces.scale(decay);
+        let z = self.get_trace(&sa.0, &sa.1);
+        self.traces
+            .update_value(&sa.0, &sa.1, z + 1f64 - alpha * decay * z_sa);
+
+        // Q <- Q + α (δ + Q - Q_old) z - α (Q - Q_old) x
+        for (x, entries) in self.traces.get_table() {
+            for (b, z) in entries {
+                let q = self.q_table.get_value(x, b).unwrap_or(0.0);
+                self.q_table
+                    .update_value(x, b, q + alpha * (delta + Q - self.q_old) * z);
+            }
+        }
+        let q = self.get_value(&sa.0, &sa.1).unwrap_or(0.0);
+        self.update_value(&sa.0, &sa.1, q - alpha * (Q - self.q_old));
+        self.q_old = Q_next;
+        self.increment_count();
+
+        if sa_next.is_none() {
+            self.reset_traces();
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A>
+    for TrueOnlineSARSALambda<S, A>
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A>
+    for TrueOnlineSARSALambda<S, A>
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        match self.next_action.take() {
+            Some((s, a)) if s == *state => Some(a),
+            _ => self.gen_action(state, rng),
+        }
+    }
+
+    /// A' is drawn here from the current policy and reused by the next `select_action` on S'
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let sa_next = next_state.and_then(|s| self.gen_action(s, rng).map(|a| (s.clone(), a)));
+        self.next_action = sa_next.clone();
+        self.update_one_step(state.clone(), action.clone(), reward, sa_next);
+        self.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.next_action = None;
+        self.reset_traces();
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        self.next_action = None;
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Off-policy Monte Carlo Control
+// └──────────────────────────────────────────────────────────┘
+/// Off-policy MC control toward the greedy policy of Q
+///
+/// Episodes are generated by the behavior policy b and processed backward at episode end.
+/// Q(S_t, A_t) is averaged over returns weighted by ρ = Π_{k>t} π(A_k|S_k) / b(A_k|S_k)
+/// where π is greedy in Q (ties split uniformly). With `ImportanceSampling::Weighted` the
+/// backward pass stops as soon as ρ becomes zero.
+pub struct OffPolicyMCControl<S, A, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    B: Policy<S, A>,
+{
+    q_table: QTable<S, A>,
+    behavior_policy: B,
+    gamma: f64,
+    sampling: ImportanceSampling,
+    weights: QTable<S, A>,
+    episode: Vec<(S, A, f64)>,
+    _random: bool,
+}
+
+impl<S, A, B> OffPolicyMCControl<S, A, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    B: Policy<S, A>,
+{
+    pub fn new(
+        q_table: QTable<S, A>,
+        behavior_policy: B,
+        gamma: f64,
+        sampling: ImportanceSampling,
+    ) -> Self {
+        OffPolicyMCControl {
+            q_table,
+            behavior_policy,
+            gamma,
+            sampling,
+            weights: QTable::new(),
+            episode: Vec::new(),
+            _random: true,
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    /// Visit count N(s, a) (ordinary) or cumulative weight C(s, a) (weighted)
+    pub fn get_weight(&self, s: &S, a: &A) -> f64 {
+        self.weights.get_value(s, a).unwrap_or(0.0)
+    }
+
+    pub fn get_behavior_policy(&self) -> &B {
+        &self.behavior_policy
+    }
+
+    pub fn update_episode(&mut self, episode: &[(S, A, f64)]) {
+        self.episode = episode.to_vec()
+    }
+
+    /// Act with the behavior policy (off: act greedily in Q)
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+}
+
+impl<S, A, B> ActionValuePredictor<S, A> for OffPolicyMCControl<S, A, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    B: Policy<S, A>,
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        if self.episode.is_empty() {
+            panic!("Episode is empty");
+        }
+
+        let episode = self.episode.clone();
+
+        // Backward pass accumulating return and importance ratio
+        let mut G = 0f64;
+        let mut W = 1f64;
+        for (s, a, r) in episode.iter().rev() {
+            G = self.gamma * G + r;
+            let q = self.get_value(s, a).unwrap_or(0.0);
+            let c = self.get_weight(s, a);
+            match self.sampling {
+                ImportanceSampling::Ordinary => {
+                    self.weights.update_value(s, a, c + 1f64);
+                    self.update_value(s, a, q + (W * G - q) / (c + 1f64));
+                }
+                ImportanceSampling::Weighted => {
+                    self.weights.update_value(s, a, c + W);
+                    self.update_value(s, a, q + W / (c + W) * (G - q));
+                }
+            }
+
+            // Ratio for the preceding step, under the updated greedy policy
+            let pi = self
+                .q_table
+                .epsilon_greedy_probs(s, 0f64)
+                .into_iter()
+                .find(|(x, _)| x == a)
+                .map_or(0f64, |(_, p)| p);
+            W *= pi / self.behavior_policy.prob(s, a);
+            if W == 0f64 && self.sampling == ImportanceSampling::Weighted {
+                break;
+            }
+        }
+    }
+}
+
+impl<S, A, B> Policy<S, A> for OffPolicyMCControl<S, A, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    B: Policy<S, A>,
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        if self._random {
+            self.behavior_policy.gen_action(state, rng)
+        } else {
+            self.q_table.epsilon_greedy_action(state, 0f64, rng)
+        }
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        if self._random {
+            self.behavior_policy.action_probs(state)
+        } else {
+            self.q_table.epsilon_greedy_probs(state, 0f64)
+        }
+    }
+}
+
+impl<S, A, B> Agent<S, A> for OffPolicyMCControl<S, A, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    B: Policy<S, A>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        _next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.episode.push((state.clone(), action.clone(), reward));
+    }
+
+    fn begin_episode(&mut self) {
+        self.episode.clear();
+    }
+
+    fn end_episode(&mut self) {
+        if self.episode.is_empty() {
+            return;
+        }
+        self.step();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Monte Carlo Exploring Starts
+// └──────────────────────────────────────────────────────────┘
+/// Monte Carlo control with exploring starts (first-visit, sample averages)
+///
+/// Acts greedily in Q (ties broken uniformly at random); exploration comes entirely from
+/// starting episodes at random `(state, action)` pairs, see `Trainer::train_exploring_starts`.
+pub struct MonteCarloES<S: Eq + std::hash::Hash + Clone, A: Eq + std::hash::Hash + Clone> {
+    q_table: QTable<S, A>,
+    gamma: f64,
+    counts: HashMap<(S, A), usize>,
+    episode: Vec<(S, A, f64)>,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Eq + std::hash::Hash + Clone> MonteCarloES<S, A> {
+    pub fn new(q_table: QTable<S, A>, gamma: f64) -> Self {
+        MonteCarloES {
+            q_table,
+            gamma,
+            counts: HashMap::new(),
+            episode: Vec::new(),
+        }
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    /// Number of first visits N(s, a) over all processed episodes
+    pub fn get_count(&self, s: &S, a: &A) -> usize {
+        self.counts
+            .get(&(s.clone(), a.clone()))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    pub fn update_episode(&mut self, episode: &[(S, A, f64)]) {
+        self.episode = episode.to_vec()
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Eq + std::hash::Hash + Clone> ActionValuePredictor<S, A>
+    for MonteCarloES<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        if self.episode.is_empty() {
+            panic!("Episode is empty");
+        }
+
+        let episode = self.episode.clone();
+
+        // Backward update for cumulative discounted return
+        let mut G: Vec<f64> = episode
+            .iter()
+            .rev()
+            .scan(0.0, |acc, (_, _, r)| {
+                *acc = *acc * self.gamma + r;
+                Some(*acc)
+            })
+            .collect();
+        G.reverse();
+
+        // Forward update for first occurrences of each (s, a)
+        let mut visited: HashSet<(&S, &A)> = HashSet::new();
+        for ((s, a, _), g) in episode.iter().zip(G) {
+            if !visited.insert((s, a)) {
+                continue;
+            }
+            let n = self.counts.entry((s.clone(), a.clone())).or_insert(0);
+            *n += 1;
+            let n = *n as f64;
+            let q = self.get_value(s, a).unwrap_or(0.0);
+            self.update_value(s, a, q + (g - q) / n);
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Eq + std::hash::Hash + Clone> Policy<S, A>
+    for MonteCarloES<S, A>
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.q_table.epsilon_greedy_action(state, 0f64, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        self.q_table.epsilon_greedy_probs(state, 0f64)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Eq + std::hash::Hash + Clone> Agent<S, A>
+    for MonteCarloES<S, A>
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        _next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.episode.push((state.clone(), action.clone(), reward));
+    }
+
+    fn begin_episode(&mut self) {
+        self.episode.clear();
+    }
+
+    fn end_episode(&mut self) {
+        if self.episode.is_empty() {
+            return;
+        }
+        self.step();
+    }
+
+    /// The policy is always greedy
+    fn set_greedy(&mut self, _greedy: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning::util::ConstantStepsize;
+
+    #[test]
+    fn sarsa_reuses_next_action_only_for_its_state() {
+        // Greedy action is 0 in state 1 and 1 in state 2; α = 0 keeps the table fixed
+        let mut q_table = QTable::new();
+        for s in 0..3 {
+            q_table.update_value(&s, &0usize, 0.0);
+            q_table.update_value(&s, &1usize, 0.0);
+        }
+        q_table.update_value(&1, &0, 1.0);
+        q_table.update_value(&2, &1, 1.0);
+        let mut agent = SARSA::new(q_table, Box::new(ConstantStepsize::new(0.0)), 1.0, 0.0);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        agent.observe(&0, &0, 0.0, Some(&1), &mut rng);
+        assert_eq!(agent.select_action(&2, &mut rng), Some(1));
+
+        agent.observe(&0, &0, 0.0, Some(&1), &mut rng);
+        assert_eq!(agent.select_action(&1, &mut rng), Some(0));
+    }
+
+    #[test]
+    fn monte_carlo_es_counts_first_visits() {
+        let mut agent = MonteCarloES::new(QTable::new(), 1.0);
+        agent.update_episode(&[(0, 0usize, 1.0), (1, 0, 1.0), (0, 0, 1.0)]);
+        agent.step();
+        assert_eq!(agent.get_count(&0, &0), 1);
+        assert_eq!(agent.get_value(&0, &0), Some(3.0));
+        assert_eq!(agent.get_value(&1, &0), Some(2.0));
+
+        agent.update_episode(&[(0, 0, 5.0)]);
+        agent.step();
+        assert_eq!(agent.get_count(&0, &0), 2);
+        assert_eq!(agent.get_count(&1, &1), 0);
+        assert_eq!(agent.get_value(&0, &0), Some(4.0));
+    }
+
+    /// Q over states {0, 1} x actions {0, 1} with action 0 greedy everywhere
+    fn two_state_q_table() -> QTable<usize, usize> {
+        let mut q_table = QTable::new();
+        for s in 0..2 {
+            q_table.update_value(&s, &0, 1.0);
+            q_table.update_value(&s, &1, 0.0);
+        }
+        q_table
+    }
+
+    #[test]
+    fn watkins_q_lambda_cuts_traces_on_exploratory_actions() {
+        // α = 0 freezes Q, so greediness does not change along the way
+        let mut agent = WatkinsQLambda::new(
+            two_state_q_table(),
+            Box::new(ConstantStepsize::new(0.0)),
+            0.9,
+            0.8,
+            0.1,
+            EligibilityTrace::Accumulating,
+        );
+        agent.update_one_step(0, 0, 0.0, Some((1, 0)));
+        agent.step();
+        assert!((agent.get_trace(&0, &0) - 0.72).abs() < 1e-12);
+
+        agent.update_one_step(1, 0, 0.0, Some((0, 1)));
+        agent.step();
+        for s in 0..2 {
+            for a in 0..2 {
+                assert_eq!(agent.get_trace(&s, &a), 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn replacing_traces_cap_at_one() {
+        // γλ = 1, so only the revisit changes the trace
+        let trace_after_revisit = |kind| {
+            let mut sarsa = SARSALambda::new(
+                two_state_q_table(),
+                Box::new(ConstantStepsize::new(0.0)),
+                1.0,
+                1.0,
+                0.1,
+                kind,
+            );
+            let mut watkins = WatkinsQLambda::new(
+                two_state_q_table(),
+                Box::new(ConstantStepsize::new(0.0)),
+                1.0,
+                1.0,
+                0.1,
+                kind,
+            );
+            for _ in 0..2 {
+                sarsa.update_one_step(0, 0, 0.0, Some((0, 0)));
+                sarsa.step();
+                watkins.update_one_step(0, 0, 0.0, Some((0, 0)));
+                watkins.step();
+            }
+            (sarsa.get_trace(&0, &0), watkins.get_trace(&0, &0))
+        };
+        assert_eq!(trace_after_revisit(EligibilityTrace::Replacing), (1.0, 1.0));
+        assert_eq!(
+            trace_after_revisit(EligibilityTrace::Accumulating),
+            (2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn lambda_agents_clear_traces_in_begin_episode() {
+        let step = || Box::new(ConstantStepsize::new(0.5));
+        let kind = EligibilityTrace::Accumulating;
+        let mut sarsa = SARSALambda::new(two_state_q_table(), step(), 0.9, 0.8, 0.1, kind);
+        let mut watkins = WatkinsQLambda::new(two_state_q_table(), step(), 0.9, 0.8, 0.1, kind);
+        let mut true_online =
+            TrueOnlineSARSALambda::new(two_state_q_table(), step(), 0.9, 0.8, 0.1);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        // A truncated episode leaves its traces behind
+        let agents: [&mut dyn Agent<usize, usize>; 3] =
+            [&mut sarsa, &mut watkins, &mut true_online];
+        for agent in agents {
+            agent.begin_episode();
+            agent.observe(&0, &0, 1.0, Some(&1), &mut rng);
+            agent.end_episode();
+        }
+        assert!(sarsa.get_trace(&0, &0) > 0.0);
+        assert!(true_online.get_trace(&0, &0) > 0.0);
+
+        sarsa.begin_episode();
+        watkins.begin_episode();
+        true_online.begin_episode();
+        for s in 0..2 {
+            for a in 0..2 {
+                assert_eq!(sarsa.get_trace(&s, &a), 0.0);
+                assert_eq!(watkins.get_trace(&s, &a), 0.0);
+                assert_eq!(true_online.get_trace(&s, &a), 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn double_q_learning_updates_one_table_from_the_other() {
+        // Q1 prefers action 0 in state 1, Q2 values it at 2 (and action 1 at 5)
+        let mut agent = DoubleQLearning::new(
+            two_state_q_table(),
+            Box::new(ConstantStepsize::new(1.0)),
+            1.0,
+            0.1,
+        );
+        agent.update_value(1, &1, &0, 2.0);
+        agent.update_value(1, &1, &1, 5.0);
+
+        agent.update_one_step(0, 0, 0.5, Some(1), 0);
+        agent.step();
+
+        // Q1(0, 0) <- R + Q2(1, argmax_a Q1(1, a)) = 0.5 + 2
+        assert_eq!(agent.get_q_table_at(0).get_value(&0, &0), Some(2.5));
+        assert_eq!(agent.get_q_table_at(1).get_value(&0, &0), Some(1.0));
+        assert_eq!(agent.get_value(&0, &0), Some(1.75));
+        for (s, a) in [(0, 1), (1, 0), (1, 1)] {
+            assert_eq!(
+                agent.get_q_table_at(0).get_value(&s, &a),
+                two_state_q_table().get_value(&s, &a)
+            );
+        }
+    }
+
+    #[test]
+    fn double_q_learning_takes_left_less_often() {
+        use crate::env::maximization_bias::{
+            MaximizationBias, MaximizationBiasAction as MBA, MaximizationBiasState,
+        };
+        use crate::learning::trainer::{EpisodeRecorder, Trainer};
+
+        /// Episodes (out of 300) that go `Left` from A
+        fn num_left<G: Agent<MaximizationBiasState, MBA>>(agent: &mut G, seed: u64) -> usize {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut recorder = EpisodeRecorder::new();
+            let trainer = Trainer::new(300, 100);
+            trainer.train(
+                &MaximizationBias::default(),
+                agent,
+                &mut rng,
+                &mut [&mut recorder],
+            );
+            recorder
+                .get_episodes()
+                .iter()
+                .filter(|episode| episode[0].1 == MBA::Left)
+                .count()
+        }
+
+        let env = MaximizationBias::default();
+        let (mut q_left, mut double_q_left) = (0, 0);
+        for seed in 0..20 {
+            let step = || Box::new(ConstantStepsize::new(0.1));
+            let q_table = QTable::from_mdp(&env, 0.0);
+            q_left += num_left(&mut QLearning::new(q_table.clone(), step(), 1.0, 0.1), seed);
+            double_q_left += num_left(&mut DoubleQLearning::new(q_table, step(), 1.0, 0.1), seed);
+        }
+        assert!(double_q_left < q_left, "{} >= {}", double_q_left, q_left);
+    }
+}
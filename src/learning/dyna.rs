@@ -0,0 +1,410 @@
+use super::control::ActionValuePredictor;
+use super::util::StepsizeScheduler;
+use crate::base::agent::Agent;
+use crate::base::function::QTable;
+use crate::base::policy::Policy;
+use crate::base::process::MarkovDecisionProcess;
+use crate::planning::model::TabularModel;
+use crate::planning::prioritized_sweeping::PrioritizedSweeping;
+use peroxide::fuga::*;
+use std::collections::HashMap;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Dyna-Q
+// └──────────────────────────────────────────────────────────┘
+/// Dyna-Q / Dyna-Q+ (tabular)
+///
+/// Every real transition gives a Q-learning update and is recorded in a `TabularModel`;
+/// then `n_planning` simulated transitions are drawn from uniformly chosen previously tried
+/// `(s, a)` pairs and used for the same update. With `kappa > 0` (Dyna-Q+) planning rewards
+/// get the bonus κ√τ, where τ is the number of real steps since `(s, a)` was last tried.
+///
+/// Real and planning updates keep separate step counts for the step-size scheduler.
+#[allow(clippy::type_complexity)]
+pub struct DynaQ<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    model: TabularModel<S, A>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    n_planning: usize,
+    kappa: f64,
+    last_tried: HashMap<S, Vec<(A, usize)>>,
+    one_step: Option<(S, A, f64, Option<S>)>,
+    _time: usize,
+    _count: usize,
+    _planning_count: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> DynaQ<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        model: TabularModel<S, A>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+        n_planning: usize,
+        kappa: f64,
+    ) -> Self {
+        DynaQ {
+            q_table,
+            model,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            n_planning,
+            kappa,
+            last_tried: HashMap::new(),
+            one_step: None,
+            _time: 0,
+            _count: 0,
+            _planning_count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_model(&self) -> &TabularModel<S, A> {
+        &self.model
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> Option<f64> {
+        self.q_table.get_value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn update_value(&mut self, s: &S, a: &A, value: f64) {
+        self.q_table.update_value(s, a, value);
+    }
+
+    pub fn update_one_step(&mut self, s: S, a: A, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, a, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+        self._planning_count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    /// Real step at which `(s, a)` was last tried (`None` if never)
+    pub fn get_last_tried(&self, s: &S, a: &A) -> Option<usize> {
+        self.last_tried
+            .get(s)
+            .and_then(|entries| entries.iter().find(|(b, _)| b == a))
+            .map(|(_, t)| *t)
+    }
+
+    /// κ√τ for `(s, a)` (0 for Dyna-Q)
+    pub fn exploration_bonus(&self, s: &S, a: &A) -> f64 {
+        if self.kappa == 0f64 {
+            return 0f64;
+        }
+        let tau = self._time - self.get_last_tried(s, a).unwrap_or(0);
+        self.kappa * (tau as f64).sqrt()
+    }
+
+    /// Record a real transition in the model
+    pub fn update_model(&mut self, s: &S, a: &A, r: f64, s_next: Option<&S>) {
+        self._time += 1;
+        let entries = self.last_tried.entry(s.clone()).or_default();
+        match entries.iter_mut().find(|(b, _)| b == a) {
+            Some((_, t)) => *t = self._time,
+            None => entries.push((a.clone(), self._time)),
+        }
+        self.model.update(s, a, r, s_next);
+    }
+
+    /// Q-learning update of the stored transition with the step size at step `t`
+    fn backup(&mut self, t: usize) {
+        let (s, a, r, s_next) = self.one_step.take().unwrap();
+        let target = match s_next {
+            Some(s_next) => r + self.gamma * self.q_table.max_value(&s_next),
+            None => r,
+        };
+        let q = self.get_value(&s, &a).unwrap_or(0.0);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(t, &sa);
+        self.update_value(&sa.0, &sa.1, q + alpha * (target - q));
+    }
+
+    /// `n_planning` Q-learning updates on transitions simulated by the model
+    pub fn plan(&mut self, rng: &mut dyn RngCore) {
+        if self.model.observed_pairs().is_empty() {
+            return;
+        }
+        for _ in 0..self.n_planning {
+            let pairs = self.model.observed_pairs();
+            let (s, a) = pairs[rng.gen_range(0..pairs.len())].clone();
+            let (s_next, r) = self.model.sample_step(&s, &a, rng);
+            let r = r + self.exploration_bonus(&s, &a);
+            self.update_one_step(s, a, r, s_next);
+            self.backup(self._planning_count);
+            self._planning_count += 1;
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for DynaQ<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        self.backup(self._count);
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A> for DynaQ<S, A> {
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A> for DynaQ<S, A> {
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        // Direct RL
+        self.update_one_step(state.clone(), action.clone(), reward, next_state.cloned());
+        self.step();
+        // Model learning
+        self.update_model(state, action, reward, next_state);
+        // Planning
+        self.plan(rng);
+    }
+
+    fn begin_episode(&mut self) {
+        self.reset_increment();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Prioritized Sweeping Agent
+// └──────────────────────────────────────────────────────────┘
+/// Learns a `TabularModel` and plans on it with `PrioritizedSweeping`
+///
+/// Each real transition updates the model, registers the predecessor and queues the pair by
+/// its Bellman error; Q is then updated only through the planner's prioritized backups.
+pub struct PrioritizedSweepingAgent<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    q_table: QTable<S, A>,
+    model: TabularModel<S, A>,
+    planner: PrioritizedSweeping<S, A>,
+    epsilon: f64,
+    _updates: usize,
+    _random: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> PrioritizedSweepingAgent<S, A> {
+    pub fn new(
+        q_table: QTable<S, A>,
+        model: TabularModel<S, A>,
+        planner: PrioritizedSweeping<S, A>,
+        epsilon: f64,
+    ) -> Self {
+        PrioritizedSweepingAgent {
+            q_table,
+            model,
+            planner,
+            epsilon,
+            _updates: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_model(&self) -> &TabularModel<S, A> {
+        &self.model
+    }
+
+    pub fn get_planner(&self) -> &PrioritizedSweeping<S, A> {
+        &self.planner
+    }
+
+    /// Total number of planning backups so far
+    pub fn get_num_updates(&self) -> usize {
+        self._updates
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> ActionValuePredictor<S, A>
+    for PrioritizedSweepingAgent<S, A>
+{
+    fn get_q_table(&self) -> &QTable<S, A> {
+        &self.q_table
+    }
+
+    fn step(&mut self) {
+        self._updates += self.planner.plan(&self.model, &mut self.q_table);
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Policy<S, A>
+    for PrioritizedSweepingAgent<S, A>
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_action(state, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        self.q_table.epsilon_greedy_probs(state, epsilon)
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Agent<S, A>
+    for PrioritizedSweepingAgent<S, A>
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.model.update(state, action, reward, next_state);
+        if let Some(s_next) = next_state {
+            self.planner.add_predecessor(state, action, s_next);
+        }
+        let priority = self
+            .planner
+            .bellman_error(&self.model, &self.q_table, state, action);
+        self.planner.push(state, action, priority);
+        self.step();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning::util::ConstantStepsize;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn dyna_q(
+        model: TabularModel<usize, usize>,
+        n_planning: usize,
+        kappa: f64,
+    ) -> DynaQ<usize, usize> {
+        let step = Box::new(ConstantStepsize::new(0.5));
+        DynaQ::new(QTable::new(), model, step, 0.9, 0.1, n_planning, kappa)
+    }
+
+    /// Constant step size that logs the step count it is asked for
+    struct StepLog(Rc<RefCell<Vec<usize>>>);
+
+    impl StepsizeScheduler<(usize, usize)> for StepLog {
+        fn stepsize(&mut self, t: usize, _sa: &(usize, usize)) -> f64 {
+            self.0.borrow_mut().push(t);
+            0.5
+        }
+    }
+
+    #[test]
+    fn bonus_grows_with_time_since_last_tried() {
+        let mut agent = dyna_q(TabularModel::new(), 0, 0.5);
+        agent.update_model(&0, &0, 0.0, Some(&0));
+        assert_eq!(agent.get_last_tried(&0, &0), Some(1));
+        assert_eq!(agent.exploration_bonus(&0, &0), 0.0);
+        // Never tried: τ counts every real step
+        assert_eq!(agent.exploration_bonus(&0, &1), 0.5);
+
+        for _ in 0..3 {
+            agent.update_model(&0, &1, 0.0, Some(&0));
+        }
+        assert_eq!(agent.get_last_tried(&0, &1), Some(4));
+        assert_eq!(agent.exploration_bonus(&0, &0), 0.5 * 3f64.sqrt());
+        assert_eq!(agent.exploration_bonus(&1, &0), 0.5 * 4f64.sqrt());
+        assert_eq!(
+            dyna_q(TabularModel::new(), 0, 0.0).exploration_bonus(&1, &0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn deterministic_model_returns_the_last_outcome() {
+        let mut agent = dyna_q(TabularModel::deterministic(), 5, 0.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        agent.observe(&0, &0, 1.0, Some(&1), &mut rng);
+        agent.observe(&0, &0, 2.0, None, &mut rng);
+
+        assert_eq!(agent.get_model().outcomes(&0, &0), &[(None, 1, 2.0)]);
+        for _ in 0..10 {
+            assert_eq!(agent.get_model().sample_step(&0, &0, &mut rng), (None, 2.0));
+        }
+    }
+
+    #[test]
+    fn planning_keeps_its_own_step_count() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let step = Box::new(StepLog(log.clone()));
+        let mut agent = DynaQ::new(QTable::new(), TabularModel::new(), step, 0.9, 0.1, 3, 0.0);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        agent.begin_episode();
+        agent.observe(&0, &0, 1.0, Some(&1), &mut rng);
+        agent.observe(&1, &0, 1.0, None, &mut rng);
+        assert_eq!(*log.borrow(), vec![1, 1, 2, 3, 2, 4, 5, 6]);
+    }
+}
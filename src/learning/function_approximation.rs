@@ -0,0 +1,627 @@
+use super::util::StepsizeScheduler;
+use crate::base::agent::Agent;
+use crate::base::function::{
+    ActionValueFunction, FeatureExtractor, LinearActionValueFunction, LinearValueFunction,
+    ValueFunction,
+};
+use crate::base::policy::Policy;
+use crate::base::process::MarkovDecisionProcess;
+use peroxide::fuga::*;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Counterpart of `ValuePredictor` for a linear value function
+pub trait ApproxValuePredictor<S, F: FeatureExtractor<S>> {
+    fn get_value_function(&self) -> &LinearValueFunction<S, F>;
+    fn step(&mut self);
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Gradient Montecarlo
+// └──────────────────────────────────────────────────────────┘
+/// Gradient Monte Carlo prediction
+///
+/// w <- w + α [G_t - v(S_t, w)] ∇v(S_t, w) for every step of a finished episode, in order.
+pub struct GradientMC<S: Clone, F: FeatureExtractor<S>> {
+    value_function: LinearValueFunction<S, F>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    episode: Vec<(S, f64)>,
+}
+
+impl<S: Clone, F: FeatureExtractor<S>> GradientMC<S, F> {
+    pub fn new(
+        value_function: LinearValueFunction<S, F>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+    ) -> Self {
+        GradientMC {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            episode: Vec::new(),
+        }
+    }
+
+    pub fn update_episode(&mut self, episode: &[(S, f64)]) {
+        self.episode = episode.to_vec()
+    }
+
+    pub fn get_value(&self, s: &S) -> f64 {
+        self.value_function.value(s)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+}
+
+impl<S: Clone, F: FeatureExtractor<S>> ApproxValuePredictor<S, F> for GradientMC<S, F> {
+    fn get_value_function(&self) -> &LinearValueFunction<S, F> {
+        &self.value_function
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        if self.episode.is_empty() {
+            panic!("Episode is empty");
+        }
+
+        let episode = std::mem::take(&mut self.episode);
+
+        // Backward update for cumulative discounted return
+        let mut G: Vec<f64> = episode
+            .iter()
+            .rev()
+            .scan(0.0, |acc, (_, r)| {
+                *acc = *acc * self.gamma + r;
+                Some(*acc)
+            })
+            .collect();
+        G.reverse();
+
+        for (t, ((s, _), g)) in episode.iter().zip(G).enumerate() {
+            let alpha = self.get_stepsize(t, s);
+            let v = self.get_value(s);
+            self.value_function.update(s, alpha * (g - v));
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Semi-gradient TD(0)
+// └──────────────────────────────────────────────────────────┘
+/// Semi-gradient TD(0) prediction
+///
+/// w <- w + α [R + γ v(S', w) - v(S, w)] ∇v(S, w) with v(terminal) = 0
+pub struct SemiGradientTD0<S, F: FeatureExtractor<S>> {
+    value_function: LinearValueFunction<S, F>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    one_step: Option<(S, f64, Option<S>)>,
+    _count: usize,
+}
+
+impl<S, F: FeatureExtractor<S>> SemiGradientTD0<S, F> {
+    pub fn new(
+        value_function: LinearValueFunction<S, F>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+    ) -> Self {
+        SemiGradientTD0 {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            one_step: None,
+            _count: 0,
+        }
+    }
+
+    pub fn get_value(&self, s: &S) -> f64 {
+        self.value_function.value(s)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+
+    pub fn update_one_step(&mut self, s: S, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+}
+
+impl<S, F: FeatureExtractor<S>> ApproxValuePredictor<S, F> for SemiGradientTD0<S, F> {
+    fn get_value_function(&self) -> &LinearValueFunction<S, F> {
+        &self.value_function
+    }
+
+    fn step(&mut self) {
+        let (s, r, s_next) = self.one_step.take().unwrap();
+        let target = match s_next {
+            Some(s_next) => r + self.gamma * self.get_value(&s_next),
+            None => r,
+        };
+        let alpha = self.get_stepsize(self._count, &s);
+        let delta = target - self.get_value(&s);
+        self.value_function.update(&s, alpha * delta);
+        self.increment_count();
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Approximate Prediction Agent
+// └──────────────────────────────────────────────────────────┘
+/// Follows a fixed policy and feeds its experience to a linear value predictor
+///
+/// The policy is not derived from the estimate, so `set_greedy` does nothing.
+pub struct ApproxPredictionAgent<S, A, P, V>
+where
+    P: Policy<S, A>,
+{
+    policy: P,
+    predictor: V,
+    episode: Vec<(S, f64)>,
+    action_type: PhantomData<A>,
+}
+
+impl<S, A, P, V> ApproxPredictionAgent<S, A, P, V>
+where
+    P: Policy<S, A>,
+{
+    pub fn new(policy: P, predictor: V) -> Self {
+        ApproxPredictionAgent {
+            policy,
+            predictor,
+            episode: Vec::new(),
+            action_type: PhantomData,
+        }
+    }
+
+    pub fn get_policy(&self) -> &P {
+        &self.policy
+    }
+
+    pub fn get_predictor(&self) -> &V {
+        &self.predictor
+    }
+}
+
+impl<S, A, P, F> Agent<S, A> for ApproxPredictionAgent<S, A, P, GradientMC<S, F>>
+where
+    S: Clone,
+    P: Policy<S, A>,
+    F: FeatureExtractor<S>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.policy.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        _action: &A,
+        reward: f64,
+        _next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.episode.push((state.clone(), reward));
+    }
+
+    fn begin_episode(&mut self) {
+        self.episode.clear();
+    }
+
+    fn end_episode(&mut self) {
+        if self.episode.is_empty() {
+            return;
+        }
+        self.predictor.update_episode(&self.episode);
+        self.predictor.step();
+    }
+
+    fn set_greedy(&mut self, _greedy: bool) {}
+}
+
+impl<S, A, P, F> Agent<S, A> for ApproxPredictionAgent<S, A, P, SemiGradientTD0<S, F>>
+where
+    S: Clone,
+    P: Policy<S, A>,
+    F: FeatureExtractor<S>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        self.policy.gen_action(state, rng)
+    }
+
+    fn observe(
+        &mut self,
+        state: &S,
+        _action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+        self.predictor
+            .update_one_step(state.clone(), reward, next_state.cloned());
+        self.predictor.step();
+    }
+
+    fn begin_episode(&mut self) {
+        self.predictor.reset_increment();
+    }
+
+    fn set_greedy(&mut self, _greedy: bool) {}
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Semi-gradient n-step SARSA
+// └──────────────────────────────────────────────────────────┘
+/// Episodic semi-gradient n-step SARSA with an ε-greedy policy over `actions_at`
+///
+/// Transitions are buffered until n rewards are available, then the oldest pair is updated:
+/// w <- w + α [G - q(S_τ, A_τ, w)] ∇q(S_τ, A_τ, w),
+/// G = R_{τ+1} + ... + γ^(n-1) R_{τ+n} + γ^n q(S_{τ+n}, A_{τ+n}, w).
+/// A terminal transition flushes the buffer with truncated returns; an episode cut off by a
+/// step cap is flushed in `end_episode`, bootstrapping from the last (S', A').
+#[allow(clippy::type_complexity)]
+pub struct SemiGradientNStepSARSA<'a, S, A, M, F>
+where
+    A: Clone + PartialEq,
+    M: MarkovDecisionProcess<S, A>,
+    F: FeatureExtractor<S>,
+{
+    mdp: &'a M,
+    q_function: LinearActionValueFunction<S, A, F>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+    gamma: f64,
+    epsilon: f64,
+    n: usize,
+    buffer: VecDeque<(S, A, f64)>,
+    last_pair: Option<(S, A)>,
+    /// A' drawn in `observe`, paired with the S' it was drawn for
+    next_action: Option<(S, A)>,
+    _count: usize,
+    _random: bool,
+}
+
+impl<'a, S, A, M, F> SemiGradientNStepSARSA<'a, S, A, M, F>
+where
+    S: Clone,
+    A: Clone + PartialEq,
+    M: MarkovDecisionProcess<S, A>,
+    F: FeatureExtractor<S>,
+{
+    pub fn new(
+        mdp: &'a M,
+        q_function: LinearActionValueFunction<S, A, F>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<(S, A)>>,
+        gamma: f64,
+        epsilon: f64,
+        n: usize,
+    ) -> Self {
+        assert!(n >= 1, "n must be at least 1");
+        SemiGradientNStepSARSA {
+            mdp,
+            q_function,
+            stepsize_scheduler,
+            gamma,
+            epsilon,
+            n,
+            buffer: VecDeque::with_capacity(n),
+            last_pair: None,
+            next_action: None,
+            _count: 0,
+            _random: true,
+        }
+    }
+
+    pub fn get_q_function(&self) -> &LinearActionValueFunction<S, A, F> {
+        &self.q_function
+    }
+
+    pub fn get_n(&self) -> usize {
+        self.n
+    }
+
+    pub fn get_value(&self, s: &S, a: &A) -> f64 {
+        self.q_function.value(s, a)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, sa: &(S, A)) -> f64 {
+        self.stepsize_scheduler.stepsize(t, sa)
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn turn_off_random(&mut self) {
+        self._random = false;
+    }
+
+    pub fn turn_on_random(&mut self) {
+        self._random = true;
+    }
+
+    /// Drop buffered transitions without updating
+    pub fn clear_buffer(&mut self) {
+        self.buffer.clear();
+        self.last_pair = None;
+    }
+
+    /// Update every buffered pair with its truncated return
+    pub fn flush(&mut self) {
+        while !self.buffer.is_empty() {
+            self.update_oldest();
+        }
+        self.last_pair = None;
+    }
+
+    /// Update the oldest buffered pair, bootstrapping from `last_pair` if any
+    #[allow(non_snake_case)]
+    fn update_oldest(&mut self) {
+        let G = self
+            .buffer
+            .iter()
+            .rev()
+            .fold(0f64, |acc, (_, _, r)| r + self.gamma * acc);
+        let bootstrap = match &self.last_pair {
+            Some((s_n, a_n)) => {
+                self.gamma.powi(self.buffer.len() as i32) * self.get_value(s_n, a_n)
+            }
+            None => 0f64,
+        };
+        let (s, a, _) = self.buffer.pop_front().unwrap();
+        let q = self.get_value(&s, &a);
+        let sa = (s, a);
+        let alpha = self.get_stepsize(self._count, &sa);
+        self.q_function
+            .update(&sa.0, &sa.1, alpha * (G + bootstrap - q));
+        self.increment_count();
+    }
+}
+
+impl<'a, S, A, M, F> Policy<S, A> for SemiGradientNStepSARSA<'a, S, A, M, F>
+where
+    S: Clone,
+    A: Clone + PartialEq,
+    M: MarkovDecisionProcess<S, A>,
+    F: FeatureExtractor<S>,
+{
+    fn gen_action(&self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        let actions = self.mdp.actions_at(state);
+        self.q_function
+            .epsilon_greedy_action(state, &actions, epsilon, rng)
+    }
+
+    fn action_probs(&self, state: &S) -> Vec<(A, f64)> {
+        let epsilon = if self._random { self.epsilon } else { 0f64 };
+        let actions = self.mdp.actions_at(state);
+        self.q_function
+            .epsilon_greedy_probs(state, &actions, epsilon)
+    }
+}
+
+impl<'a, S, A, M, F> Agent<S, A> for SemiGradientNStepSARSA<'a, S, A, M, F>
+where
+    S: Clone + PartialEq,
+    A: Clone + PartialEq,
+    M: MarkovDecisionProcess<S, A>,
+    F: FeatureExtractor<S>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        match self.next_action.take() {
+            Some((s, a)) if s == *state => Some(a),
+            _ => self.gen_action(state, rng),
+        }
+    }
+
+    /// A' is drawn here from the current policy and reused by the next `select_action` on S'
+    fn observe(
+        &mut self,
+        state: &S,
+        action: &A,
+        reward: f64,
+        next_state: Option<&S>,
+        rng: &mut dyn RngCore,
+    ) {
+        let sa_next = next_state.and_then(|s| self.gen_action(s, rng).map(|a| (s.clone(), a)));
+        self.next_action = sa_next.clone();
+        self.buffer
+            .push_back((state.clone(), action.clone(), reward));
+        self.last_pair = sa_next;
+        match self.last_pair {
+            Some(_) => {
+                if self.buffer.len() >= self.n {
+                    self.update_oldest();
+                }
+            }
+            None => self.flush(),
+        }
+    }
+
+    fn begin_episode(&mut self) {
+        self.next_action = None;
+        self.clear_buffer();
+        self.reset_increment();
+    }
+
+    fn end_episode(&mut self) {
+        // Truncated episodes still hold up to n-1 pending updates
+        self.flush();
+    }
+
+    fn set_greedy(&mut self, greedy: bool) {
+        self.next_action = None;
+        if greedy {
+            self.turn_off_random();
+        } else {
+            self.turn_on_random();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::function::TabularFeatures;
+    use crate::learning::util::ConstantStepsize;
+    use crate::learning::value_prediction::{EveryvisitMC, ValuePredictor, TD0};
+    use std::collections::HashMap;
+
+    /// (state, reward, next state); state 2 is left by a terminal step
+    type Transition = (usize, f64, Option<usize>);
+
+    const EPISODES: [&[Transition]; 3] = [
+        &[(0, 1.0, Some(1)), (1, 2.0, Some(2)), (2, 3.0, None)],
+        &[
+            (1, -1.0, Some(0)),
+            (0, 0.5, Some(1)),
+            (1, 2.0, Some(2)),
+            (2, 1.0, None),
+        ],
+        &[(2, 4.0, None)],
+    ];
+
+    const INIT: [f64; 3] = [0.5, -1.0, 2.0];
+
+    fn tabular_values() -> LinearValueFunction<usize, TabularFeatures<usize>> {
+        let mut value_function = LinearValueFunction::new(TabularFeatures::new(vec![0, 1, 2]), 0.0);
+        value_function.set_weights(INIT.to_vec());
+        value_function
+    }
+
+    fn table() -> HashMap<usize, f64> {
+        INIT.iter().cloned().enumerate().collect()
+    }
+
+    fn assert_same_values(
+        approx: &LinearValueFunction<usize, TabularFeatures<usize>>,
+        tabular: &HashMap<usize, f64>,
+    ) {
+        for s in 0..3 {
+            assert!(
+                (approx.value(&s) - tabular[&s]).abs() < 1e-12,
+                "V({}) = {} vs {}",
+                s,
+                approx.value(&s),
+                tabular[&s]
+            );
+        }
+    }
+
+    #[test]
+    fn tabular_semi_gradient_td0_is_td0() {
+        let step = || Box::new(ConstantStepsize::new(0.3));
+        let mut approx = SemiGradientTD0::new(tabular_values(), step(), 0.9);
+        let mut tabular = TD0::new(table(), step(), 0.9);
+        for episode in EPISODES {
+            approx.reset_increment();
+            tabular.reset_increment();
+            for (s, r, s_next) in episode {
+                approx.update_one_step(*s, *r, *s_next);
+                approx.step();
+                tabular.update_one_step(*s, *r, *s_next);
+                tabular.step();
+            }
+            assert_same_values(approx.get_value_function(), tabular.get_value_function());
+        }
+    }
+
+    #[test]
+    fn tabular_gradient_mc_is_every_visit_mc() {
+        let step = || Box::new(ConstantStepsize::new(0.3));
+        let mut approx = GradientMC::new(tabular_values(), step(), 0.9);
+        let mut tabular = EveryvisitMC::new(table(), step(), 0.9);
+        for episode in EPISODES {
+            let rewards: Vec<(usize, f64)> = episode.iter().map(|(s, r, _)| (*s, *r)).collect();
+            approx.update_episode(&rewards);
+            approx.step();
+            tabular.update_episode(&rewards);
+            tabular.step();
+            assert_same_values(approx.get_value_function(), tabular.get_value_function());
+        }
+    }
+
+    /// Endless chain with a single action: s -> s + 1
+    struct Chain;
+
+    impl MarkovDecisionProcess<usize, usize> for Chain {
+        fn states(&self) -> Vec<usize> {
+            (0..4).collect()
+        }
+
+        fn actions(&self) -> Vec<usize> {
+            vec![0]
+        }
+
+        fn actions_at(&self, _state: &usize) -> Vec<usize> {
+            self.actions()
+        }
+
+        fn reward(&self, _state: &usize, _action: &usize) -> f64 {
+            0.0
+        }
+
+        fn transition(&self, state: &usize, _action: &usize) -> Option<usize> {
+            Some(state + 1)
+        }
+    }
+
+    /// α = 1, γ = 0.5 and q = 1 everywhere
+    fn n_step_sarsa(
+        n: usize,
+    ) -> SemiGradientNStepSARSA<'static, usize, usize, Chain, TabularFeatures<usize>> {
+        let q_function =
+            LinearActionValueFunction::new(TabularFeatures::from_mdp(&Chain), vec![0], 1.0);
+        let step = Box::new(ConstantStepsize::new(1.0));
+        SemiGradientNStepSARSA::new(&Chain, q_function, step, 0.5, 0.0, n)
+    }
+
+    #[test]
+    fn n_step_sarsa_bootstraps_with_gamma_to_the_n() {
+        let mut agent = n_step_sarsa(2);
+        let mut rng = StdRng::seed_from_u64(0);
+        agent.begin_episode();
+        agent.observe(&0, &0, 1.0, Some(&1), &mut rng);
+        assert_eq!(agent.get_value(&0, &0), 1.0);
+
+        // G = 1 + 0.5 * 2 + 0.5² q(2, 0)
+        agent.observe(&1, &0, 2.0, Some(&2), &mut rng);
+        assert_eq!(agent.get_value(&0, &0), 2.25);
+        assert_eq!(agent.get_value(&1, &0), 1.0);
+    }
+
+    #[test]
+    fn n_step_sarsa_flushes_pending_updates_at_episode_end() {
+        let mut agent = n_step_sarsa(3);
+        let mut rng = StdRng::seed_from_u64(0);
+        agent.begin_episode();
+        agent.observe(&0, &0, 1.0, Some(&1), &mut rng);
+        agent.observe(&1, &0, 2.0, Some(&2), &mut rng);
+        assert_eq!(agent.get_value(&0, &0), 1.0);
+        assert_eq!(agent.get_value(&1, &0), 1.0);
+
+        // Truncated: both pending pairs bootstrap from q(2, 0) = 1
+        agent.end_episode();
+        assert_eq!(agent.get_value(&0, &0), 2.25);
+        assert_eq!(agent.get_value(&1, &0), 2.5);
+        assert_eq!(agent.get_value(&2, &0), 1.0);
+
+        // Nothing is left to flush
+        agent.end_episode();
+        assert_eq!(agent.get_value(&0, &0), 2.25);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod agent;
+pub mod control;
+pub mod dyna;
+pub mod function_approximation;
+pub mod trainer;
 pub mod util;
 pub mod value_prediction;
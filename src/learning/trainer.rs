@@ -0,0 +1,601 @@
+use crate::base::agent::Agent;
+use crate::base::process::{EpisodicProcess, ExploringStarts};
+use indicatif::{ProgressBar, ProgressStyle};
+use peroxide::fuga::*;
+use std::error::Error;
+use std::time::Instant;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Callback
+// └──────────────────────────────────────────────────────────┘
+/// Hooks invoked by `Trainer` during training
+///
+/// Episodes are passed as `(S, A, R)` triples in the order they were taken.
+pub trait Callback<S, A> {
+    fn on_train_begin(&mut self, _num_episodes: usize) {}
+    fn on_step(&mut self, _state: &S, _action: &A, _reward: f64, _next_state: Option<&S>) {}
+    fn on_episode_end(&mut self, _episode: usize, _trajectory: &[(S, A, f64)]) {}
+    fn on_train_end(&mut self, _history: &TrainingHistory) {}
+}
+
+/// Progress bar showing the length of the last episode
+pub struct ProgressBarCallback {
+    pb: Option<ProgressBar>,
+}
+
+impl ProgressBarCallback {
+    pub fn new() -> Self {
+        ProgressBarCallback { pb: None }
+    }
+}
+
+impl Default for ProgressBarCallback {
+    fn default() -> Self {
+        ProgressBarCallback::new()
+    }
+}
+
+impl<S, A> Callback<S, A> for ProgressBarCallback {
+    fn on_train_begin(&mut self, num_episodes: usize) {
+        let pb = ProgressBar::new(num_episodes as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+                .unwrap()
+                .progress_chars("##-"),
+        );
+        self.pb = Some(pb);
+    }
+
+    fn on_episode_end(&mut self, _episode: usize, trajectory: &[(S, A, f64)]) {
+        if let Some(pb) = &self.pb {
+            pb.inc(1);
+            pb.set_message(format!("Episode length: {}", trajectory.len()));
+        }
+    }
+
+    fn on_train_end(&mut self, _history: &TrainingHistory) {
+        if let Some(pb) = self.pb.take() {
+            pb.finish();
+        }
+    }
+}
+
+/// Keeps the trajectory of every episode
+pub struct EpisodeRecorder<S, A> {
+    episodes: Vec<Vec<(S, A, f64)>>,
+}
+
+impl<S: Clone, A: Clone> EpisodeRecorder<S, A> {
+    pub fn new() -> Self {
+        EpisodeRecorder { episodes: vec![] }
+    }
+
+    pub fn get_episodes(&self) -> &[Vec<(S, A, f64)>] {
+        &self.episodes
+    }
+
+    pub fn get_episode(&self, episode: usize) -> Option<&[(S, A, f64)]> {
+        self.episodes.get(episode).map(|e| e.as_slice())
+    }
+}
+
+impl<S: Clone, A: Clone> Default for EpisodeRecorder<S, A> {
+    fn default() -> Self {
+        EpisodeRecorder::new()
+    }
+}
+
+impl<S: Clone, A: Clone> Callback<S, A> for EpisodeRecorder<S, A> {
+    fn on_train_begin(&mut self, _num_episodes: usize) {
+        self.episodes.clear();
+    }
+
+    fn on_episode_end(&mut self, _episode: usize, trajectory: &[(S, A, f64)]) {
+        self.episodes.push(trajectory.to_vec());
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Training History
+// └──────────────────────────────────────────────────────────┘
+/// Per-episode statistics collected by `Trainer::train`
+///
+/// Returns are undiscounted sums of rewards; times are wall-clock seconds.
+#[derive(Debug, Clone, Default)]
+pub struct TrainingHistory {
+    returns: Vec<f64>,
+    lengths: Vec<usize>,
+    episode_times: Vec<f64>,
+    wall_time: f64,
+}
+
+impl TrainingHistory {
+    pub fn get_returns(&self) -> &[f64] {
+        &self.returns
+    }
+
+    pub fn get_lengths(&self) -> &[usize] {
+        &self.lengths
+    }
+
+    pub fn get_episode_times(&self) -> &[f64] {
+        &self.episode_times
+    }
+
+    pub fn get_wall_time(&self) -> f64 {
+        self.wall_time
+    }
+
+    pub fn to_dataframe(&self) -> DataFrame {
+        let mut df = DataFrame::new(vec![]);
+        df.push("return", Series::new(self.returns.clone()));
+        df.push(
+            "length",
+            Series::new(self.lengths.iter().map(|l| *l as u64).collect::<Vec<u64>>()),
+        );
+        df.push("time", Series::new(self.episode_times.clone()));
+        df
+    }
+
+    pub fn write_parquet(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        self.to_dataframe()
+            .write_parquet(file_path, CompressionOptions::Uncompressed)
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Trainer
+// └──────────────────────────────────────────────────────────┘
+/// Episode runner driving any `Agent` on any `EpisodicProcess`
+///
+/// Each episode runs until a terminal transition or `max_step` steps (or the environment's
+/// `time_limit`, whichever is smaller). Environment and agent draw from the same `rng`, so a
+/// seeded `StdRng` reproduces a whole run.
+#[derive(Debug, Clone)]
+pub struct Trainer {
+    num_episodes: usize,
+    max_step: usize,
+}
+
+impl Trainer {
+    pub fn new(num_episodes: usize, max_step: usize) -> Self {
+        Trainer {
+            num_episodes,
+            max_step,
+        }
+    }
+
+    pub fn get_num_episodes(&self) -> usize {
+        self.num_episodes
+    }
+
+    pub fn get_max_step(&self) -> usize {
+        self.max_step
+    }
+
+    pub fn train<S, A, M, G>(
+        &self,
+        env: &M,
+        agent: &mut G,
+        rng: &mut dyn RngCore,
+        callbacks: &mut [&mut dyn Callback<S, A>],
+    ) -> TrainingHistory
+    where
+        S: Clone,
+        A: Clone,
+        M: EpisodicProcess<S, A>,
+        G: Agent<S, A>,
+    {
+        self.train_from(env, agent, rng, callbacks, &mut |rng: &mut dyn RngCore| {
+            (env.init_state(rng), None)
+        })
+    }
+
+    /// Train with every episode starting from `env.exploring_start`
+    ///
+    /// The sampled first action is taken without consulting the agent; the agent still
+    /// observes the resulting transition.
+    pub fn train_exploring_starts<S, A, M, G>(
+        &self,
+        env: &M,
+        agent: &mut G,
+        rng: &mut dyn RngCore,
+        callbacks: &mut [&mut dyn Callback<S, A>],
+    ) -> TrainingHistory
+    where
+        S: Clone,
+        A: Clone,
+        M: ExploringStarts<S, A>,
+        G: Agent<S, A>,
+    {
+        self.train_from(env, agent, rng, callbacks, &mut |rng: &mut dyn RngCore| {
+            let (s, a) = env.exploring_start(rng);
+            (s, Some(a))
+        })
+    }
+
+    /// Training loop with episodes starting from `init` (first action optional)
+    #[allow(clippy::type_complexity)]
+    fn train_from<S, A, M, G>(
+        &self,
+        env: &M,
+        agent: &mut G,
+        rng: &mut dyn RngCore,
+        callbacks: &mut [&mut dyn Callback<S, A>],
+        init: &mut dyn FnMut(&mut dyn RngCore) -> (S, Option<A>),
+    ) -> TrainingHistory
+    where
+        S: Clone,
+        A: Clone,
+        M: EpisodicProcess<S, A>,
+        G: Agent<S, A>,
+    {
+        let mut history = TrainingHistory::default();
+        callbacks
+            .iter_mut()
+            .for_each(|c| c.on_train_begin(self.num_episodes));
+
+        let start = Instant::now();
+        for episode in 0..self.num_episodes {
+            let episode_start = Instant::now();
+            let (init_state, init_action) = init(rng);
+            let trajectory =
+                self.run_episode(env, agent, init_state, init_action, true, rng, callbacks);
+
+            history
+                .returns
+                .push(trajectory.iter().map(|(_, _, r)| r).sum());
+            history.lengths.push(trajectory.len());
+            history
+                .episode_times
+                .push(episode_start.elapsed().as_secs_f64());
+            callbacks
+                .iter_mut()
+                .for_each(|c| c.on_episode_end(episode, &trajectory));
+        }
+        history.wall_time = start.elapsed().as_secs_f64();
+
+        callbacks.iter_mut().for_each(|c| c.on_train_end(&history));
+        history
+    }
+
+    /// Greedy rollout without learning
+    ///
+    /// The agent is put in greedy mode for the rollout and left in exploratory mode
+    /// (`set_greedy(false)`) afterwards, whatever its mode was before.
+    pub fn evaluate<S, A, M, G>(
+        &self,
+        env: &M,
+        agent: &mut G,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(S, A, f64)>
+    where
+        S: Clone,
+        A: Clone,
+        M: EpisodicProcess<S, A>,
+        G: Agent<S, A>,
+    {
+        agent.set_greedy(true);
+        let init_state = env.init_state(rng);
+        let trajectory = self.run_episode(env, agent, init_state, None, false, rng, &mut []);
+        agent.set_greedy(false);
+        trajectory
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn run_episode<S, A, M, G>(
+        &self,
+        env: &M,
+        agent: &mut G,
+        init_state: S,
+        mut init_action: Option<A>,
+        learn: bool,
+        rng: &mut dyn RngCore,
+        callbacks: &mut [&mut dyn Callback<S, A>],
+    ) -> Vec<(S, A, f64)>
+    where
+        S: Clone,
+        A: Clone,
+        M: EpisodicProcess<S, A>,
+        G: Agent<S, A>,
+    {
+        let mut trajectory = vec![];
+        let mut current_state = init_state;
+        if learn {
+            agent.begin_episode();
+        }
+        let max_step = env
+            .time_limit()
+            .map_or(self.max_step, |limit| limit.min(self.max_step));
+        for _ in 0..max_step {
+            let Some(action) = init_action
+                .take()
+                .or_else(|| agent.select_action(&current_state, rng))
+            else {
+                break;
+            };
+            let (s_next, r) = env.sample_step(&current_state, &action, rng);
+            if learn {
+                agent.observe(&current_state, &action, r, s_next.as_ref(), rng);
+            }
+            callbacks
+                .iter_mut()
+                .for_each(|c| c.on_step(&current_state, &action, r, s_next.as_ref()));
+            trajectory.push((current_state, action, r));
+            match s_next {
+                Some(s) => current_state = s,
+                None => break,
+            }
+        }
+        if learn {
+            agent.end_episode();
+        }
+        trajectory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::process::MarkovDecisionProcess;
+
+    /// Walk on 0..5 that moves as intended with probability 0.8 and the other way otherwise;
+    /// leaving either end terminates with reward 1 (right) or 0 (left)
+    struct Walk {
+        time_limit: Option<usize>,
+    }
+
+    impl MarkovDecisionProcess<i32, i32> for Walk {
+        fn states(&self) -> Vec<i32> {
+            (0..5).collect()
+        }
+
+        fn actions(&self) -> Vec<i32> {
+            vec![-1, 1]
+        }
+
+        fn actions_at(&self, _state: &i32) -> Vec<i32> {
+            self.actions()
+        }
+
+        fn reward(&self, state: &i32, action: &i32) -> f64 {
+            if state + action == 5 {
+                1.0
+            } else {
+                0.0
+            }
+        }
+
+        fn transition(&self, state: &i32, action: &i32) -> Option<i32> {
+            Some(state + action).filter(|s| (0..5).contains(s))
+        }
+
+        fn transition_probs(&self, state: &i32, action: &i32) -> Vec<(Option<i32>, f64, f64)> {
+            vec![
+                (
+                    self.transition(state, action),
+                    0.8,
+                    self.reward(state, action),
+                ),
+                (
+                    self.transition(state, &-action),
+                    0.2,
+                    self.reward(state, &-action),
+                ),
+            ]
+        }
+    }
+
+    impl EpisodicProcess<i32, i32> for Walk {
+        fn init_state(&self, _rng: &mut dyn RngCore) -> i32 {
+            2
+        }
+
+        fn time_limit(&self) -> Option<usize> {
+            self.time_limit
+        }
+    }
+
+    impl ExploringStarts<i32, i32> for Walk {
+        /// Always the left end, pushing right
+        fn exploring_start(&self, _rng: &mut dyn RngCore) -> (i32, i32) {
+            (0, 1)
+        }
+    }
+
+    /// Uniformly random agent that always steps left in greedy mode and logs its calls
+    #[derive(Default)]
+    struct RandomAgent {
+        greedy: bool,
+        observed: Vec<(i32, i32)>,
+        episodes: usize,
+    }
+
+    impl Agent<i32, i32> for RandomAgent {
+        fn select_action(&mut self, _state: &i32, rng: &mut dyn RngCore) -> Option<i32> {
+            if self.greedy || rng.gen::<bool>() {
+                Some(-1)
+            } else {
+                Some(1)
+            }
+        }
+
+        fn observe(
+            &mut self,
+            state: &i32,
+            action: &i32,
+            _reward: f64,
+            _next_state: Option<&i32>,
+            _rng: &mut dyn RngCore,
+        ) {
+            self.observed.push((*state, *action));
+        }
+
+        fn end_episode(&mut self) {
+            self.episodes += 1;
+        }
+
+        fn set_greedy(&mut self, greedy: bool) {
+            self.greedy = greedy;
+        }
+    }
+
+    /// Counts `on_step` calls
+    #[derive(Default)]
+    struct StepCounter {
+        steps: usize,
+    }
+
+    impl Callback<i32, i32> for StepCounter {
+        fn on_step(&mut self, _state: &i32, _action: &i32, _reward: f64, _next: Option<&i32>) {
+            self.steps += 1;
+        }
+    }
+
+    #[test]
+    fn episodes_stop_at_the_smaller_step_limit() {
+        // Fewer than 3 steps from 2 never leave the walk
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut agent = RandomAgent::default();
+        let env = Walk {
+            time_limit: Some(1),
+        };
+        let history = Trainer::new(5, 2).train(&env, &mut agent, &mut rng, &mut []);
+        assert_eq!(history.get_lengths(), &[1; 5]);
+
+        let env = Walk {
+            time_limit: Some(100),
+        };
+        let history = Trainer::new(5, 2).train(&env, &mut agent, &mut rng, &mut []);
+        assert_eq!(history.get_lengths(), &[2; 5]);
+    }
+
+    #[test]
+    fn exploring_starts_take_the_sampled_action() {
+        let env = Walk { time_limit: None };
+        let mut agent = RandomAgent {
+            greedy: true,
+            ..Default::default()
+        };
+        let mut recorder = EpisodeRecorder::new();
+        let mut rng = StdRng::seed_from_u64(0);
+        Trainer::new(10, 1).train_exploring_starts(
+            &env,
+            &mut agent,
+            &mut rng,
+            &mut [&mut recorder],
+        );
+        // The greedy agent would push left; the first step comes from the start pair instead
+        for episode in recorder.get_episodes() {
+            assert_eq!((episode[0].0, episode[0].1), (0, 1));
+        }
+        assert_eq!(agent.observed, vec![(0, 1); 10]);
+    }
+
+    #[test]
+    fn callbacks_see_every_step() {
+        let env = Walk { time_limit: None };
+        let mut agent = RandomAgent::default();
+        let mut counter = StepCounter::default();
+        let mut recorder = EpisodeRecorder::new();
+        let mut rng = StdRng::seed_from_u64(1);
+        let history = Trainer::new(30, 50).train(
+            &env,
+            &mut agent,
+            &mut rng,
+            &mut [&mut counter, &mut recorder],
+        );
+        let total: usize = history.get_lengths().iter().sum();
+        assert_eq!(counter.steps, total);
+        assert_eq!(agent.observed.len(), total);
+        assert_eq!(agent.episodes, 30);
+        assert_eq!(recorder.get_episodes().len(), 30);
+        for (episode, length) in recorder.get_episodes().iter().zip(history.get_lengths()) {
+            assert_eq!(episode.len(), *length);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_history() {
+        let env = Walk { time_limit: None };
+        let run = |seed| {
+            let mut agent = RandomAgent::default();
+            let mut rng = StdRng::seed_from_u64(seed);
+            let history = Trainer::new(50, 50).train(&env, &mut agent, &mut rng, &mut []);
+            (
+                history.get_returns().to_vec(),
+                history.get_lengths().to_vec(),
+                agent.observed,
+            )
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn evaluate_leaves_the_agent_exploring() {
+        let env = Walk { time_limit: None };
+        let mut agent = RandomAgent {
+            greedy: true,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let episode = Trainer::new(1, 10).evaluate(&env, &mut agent, &mut rng);
+        assert!(episode.iter().all(|(_, a, _)| *a == -1));
+        assert!(agent.observed.is_empty());
+        assert!(!agent.greedy);
+    }
+
+    /// Always plays the same action
+    struct Constant<A>(A);
+
+    impl<S, A: Clone> Agent<S, A> for Constant<A> {
+        fn select_action(&mut self, _state: &S, _rng: &mut dyn RngCore) -> Option<A> {
+            Some(self.0.clone())
+        }
+
+        fn observe(
+            &mut self,
+            _state: &S,
+            _action: &A,
+            _reward: f64,
+            _next_state: Option<&S>,
+            _rng: &mut dyn RngCore,
+        ) {
+        }
+
+        fn set_greedy(&mut self, _greedy: bool) {}
+    }
+
+    #[test]
+    fn classic_control_time_limits_cut_episodes() {
+        use crate::env::acrobot::{Acrobot, AcrobotAction};
+        use crate::env::cart_pole::{CartPole, CartPoleAction};
+        use crate::env::mountain_car::{MountainCar, MountainCarAction};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let trainer = Trainer::new(3, 1000);
+
+        // Coasting never reaches the goal
+        let env = MountainCar::default();
+        let mut agent = Constant(MountainCarAction::Coast);
+        let history = trainer.train(&env, &mut agent, &mut rng, &mut []);
+        assert_eq!(history.get_lengths(), &[200; 3]);
+
+        // Too short for the pole to fall or the acrobot to swing up
+        let env = CartPole::new(ExMethod::RK4, 1, Some(5));
+        let mut agent = Constant(CartPoleAction::Left);
+        let history = trainer.train(&env, &mut agent, &mut rng, &mut []);
+        assert_eq!(history.get_lengths(), &[5; 3]);
+
+        let env = Acrobot::new(ExMethod::RK4, 4, Some(5));
+        let mut agent = Constant(AcrobotAction::Zero);
+        let history = trainer.train(&env, &mut agent, &mut rng, &mut []);
+        assert_eq!(history.get_lengths(), &[5; 3]);
+
+        // The trainer's own cap still applies when it is the smaller one
+        let history = Trainer::new(3, 2).train(&env, &mut agent, &mut rng, &mut []);
+        assert_eq!(history.get_lengths(), &[2; 3]);
+    }
+}
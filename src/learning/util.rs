@@ -91,3 +91,17 @@ impl<S: Eq + std::hash::Hash + Clone> StepsizeScheduler<S> for CountDecay<S> {
         self.c / *count as f64
     }
 }
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Importance Sampling
+// └──────────────────────────────────────────────────────────┘
+/// Estimator for off-policy Monte Carlo methods
+///
+/// With returns G_i and importance ratios ρ_i observed at a state:
+/// - `Ordinary`: V = Σ ρ_i G_i / N (unbiased, possibly unbounded variance)
+/// - `Weighted`: V = Σ ρ_i G_i / Σ ρ_i (biased, bounded variance)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImportanceSampling {
+    Ordinary,
+    Weighted,
+}
@@ -1,11 +1,25 @@
-use super::util::StepsizeScheduler;
-use std::collections::HashMap;
+use super::util::{ImportanceSampling, StepsizeScheduler};
+use crate::base::policy::Policy;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub trait ValuePredictor<S> {
     fn get_value_function(&self) -> &HashMap<S, f64>;
     fn step(&mut self);
 }
 
+/// Predictor fed one transition at a time
+///
+/// Each predictor decides how a transition is stored and when `step` fires: Monte Carlo
+/// methods buffer the episode and update in `finish_episode`, TD methods update in `feed`.
+pub trait TransitionPredictor<S>: ValuePredictor<S> {
+    /// Reset per-episode state (buffers, traces, step counters)
+    fn start_episode(&mut self) {}
+    /// `next_state = None` marks a terminal transition
+    fn feed(&mut self, state: &S, reward: f64, next_state: Option<&S>);
+    /// End of an episode, terminal or truncated
+    fn finish_episode(&mut self) {}
+}
+
 // ┌──────────────────────────────────────────────────────────┐
 //  Every-visit Montecarlo
 // └──────────────────────────────────────────────────────────┘
@@ -62,7 +76,7 @@ impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for EveryvisitMC<S> {
         let episode = self.episode.clone();
 
         // Backward update for cumulative discounted return
-        let R: Vec<f64> = episode
+        let mut R: Vec<f64> = episode
             .iter()
             .rev()
             .scan(0.0, |acc, (_, r)| {
@@ -70,6 +84,7 @@ impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for EveryvisitMC<S> {
                 Some(*acc)
             })
             .collect();
+        R.reverse();
 
         // Forward update for value function
         episode
@@ -84,6 +99,149 @@ impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for EveryvisitMC<S> {
     }
 }
 
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for EveryvisitMC<S> {
+    fn start_episode(&mut self) {
+        self.episode.clear();
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, _next_state: Option<&S>) {
+        self.episode.push((state.clone(), reward));
+    }
+
+    fn finish_episode(&mut self) {
+        if !self.episode.is_empty() {
+            self.step();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  First-visit Montecarlo
+// └──────────────────────────────────────────────────────────┘
+/// First-visit Monte Carlo prediction
+///
+/// Only the first occurrence of each state in an episode is updated. With sample averaging
+/// turned on, the step size is exactly 1/N(s) (N(s): number of first visits so far) and the
+/// scheduler is bypassed.
+pub struct FirstvisitMC<S: Eq + std::hash::Hash + Clone> {
+    value_function: HashMap<S, f64>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    episode: Vec<(S, f64)>,
+    visit_counts: HashMap<S, usize>,
+    _sample_average: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone> FirstvisitMC<S> {
+    pub fn new(
+        value_function: HashMap<S, f64>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+    ) -> Self {
+        FirstvisitMC {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            episode: Vec::new(),
+            visit_counts: HashMap::new(),
+            _sample_average: false,
+        }
+    }
+
+    pub fn update_episode(&mut self, episode: &[(S, f64)]) {
+        self.episode = episode.to_vec()
+    }
+
+    pub fn get_value(&self, s: &S) -> Option<f64> {
+        self.value_function.get(s).cloned()
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+
+    pub fn update_value(&mut self, state: &S, value: f64) {
+        self.value_function.insert(state.clone(), value);
+    }
+
+    /// Number of first visits to `s` over all processed episodes
+    pub fn get_visit_count(&self, s: &S) -> usize {
+        self.visit_counts.get(s).cloned().unwrap_or(0)
+    }
+
+    pub fn turn_on_sample_average(&mut self) {
+        self._sample_average = true;
+    }
+
+    pub fn turn_off_sample_average(&mut self) {
+        self._sample_average = false;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for FirstvisitMC<S> {
+    fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        let l = self.episode.len();
+        if l == 0 {
+            panic!("Episode is empty");
+        }
+
+        let episode = self.episode.clone();
+
+        // Backward update for cumulative discounted return
+        let mut R: Vec<f64> = episode
+            .iter()
+            .rev()
+            .scan(0.0, |acc, (_, r)| {
+                *acc = *acc * self.gamma + r;
+                Some(*acc)
+            })
+            .collect();
+        R.reverse();
+
+        // Forward update for value function (first occurrence only)
+        let mut visited = HashSet::new();
+        episode
+            .iter()
+            .zip(R)
+            .enumerate()
+            .for_each(|(t, ((s, _), r))| {
+                if !visited.insert(s.clone()) {
+                    return;
+                }
+                let count = self.visit_counts.entry(s.clone()).or_insert(0);
+                *count += 1;
+                let alpha = if self._sample_average {
+                    1f64 / *count as f64
+                } else {
+                    self.get_stepsize(t, s)
+                };
+                let v = self.get_value(s).unwrap_or(0.0);
+                self.update_value(s, v + alpha * (r - v))
+            })
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for FirstvisitMC<S> {
+    fn start_episode(&mut self) {
+        self.episode.clear();
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, _next_state: Option<&S>) {
+        self.episode.push((state.clone(), reward));
+    }
+
+    fn finish_episode(&mut self) {
+        if !self.episode.is_empty() {
+            self.step();
+        }
+    }
+}
+
 // ┌──────────────────────────────────────────────────────────┐
 //  Temporal Difference Learning (TD(0))
 // └──────────────────────────────────────────────────────────┘
@@ -156,3 +314,934 @@ impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for TD0<S> {
         self.increment_count();
     }
 }
+
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for TD0<S> {
+    fn start_episode(&mut self) {
+        self.reset_increment();
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, next_state: Option<&S>) {
+        self.update_one_step(state.clone(), reward, next_state.cloned());
+        self.step();
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  n-step Temporal Difference Learning
+// └──────────────────────────────────────────────────────────┘
+/// n-step TD prediction
+///
+/// Transitions are buffered until n rewards are available, then the oldest state is updated
+/// toward G = r_1 + γr_2 + ... + γ^(n-1)r_n + γ^n V(s_n). A terminal transition flushes the
+/// buffer with truncated returns; `flush` does the same for episodes cut off by a step cap,
+/// bootstrapping from the last observed next state.
+pub struct NStepTD<S: Eq + std::hash::Hash + Clone> {
+    value_function: HashMap<S, f64>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    n: usize,
+    one_step: Option<(S, f64, Option<S>)>,
+    buffer: VecDeque<(S, f64)>,
+    last_state: Option<S>,
+    _count: usize,
+}
+
+impl<S: Eq + std::hash::Hash + Clone> NStepTD<S> {
+    pub fn new(
+        value_function: HashMap<S, f64>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+        n: usize,
+    ) -> Self {
+        assert!(n >= 1, "n must be at least 1");
+        NStepTD {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            n,
+            one_step: None,
+            buffer: VecDeque::with_capacity(n),
+            last_state: None,
+            _count: 0,
+        }
+    }
+
+    pub fn get_n(&self) -> usize {
+        self.n
+    }
+
+    pub fn get_value(&self, s: &S) -> Option<f64> {
+        self.value_function.get(s).cloned()
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+
+    pub fn update_value(&mut self, state: &S, value: f64) {
+        self.value_function.insert(state.clone(), value);
+    }
+
+    pub fn update_one_step(&mut self, s: S, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    /// Drop buffered transitions without updating
+    pub fn clear_buffer(&mut self) {
+        self.buffer.clear();
+        self.last_state = None;
+    }
+
+    /// Update every buffered state with its truncated return
+    pub fn flush(&mut self) {
+        while !self.buffer.is_empty() {
+            self.update_oldest();
+        }
+        self.last_state = None;
+    }
+
+    /// Update the oldest buffered state, bootstrapping from `last_state` if any
+    #[allow(non_snake_case)]
+    fn update_oldest(&mut self) {
+        let G = self
+            .buffer
+            .iter()
+            .rev()
+            .fold(0f64, |acc, (_, r)| r + self.gamma * acc);
+        let bootstrap = match &self.last_state {
+            Some(s_n) => {
+                self.gamma.powi(self.buffer.len() as i32) * self.get_value(s_n).unwrap_or(0.0)
+            }
+            None => 0f64,
+        };
+        let (s, _) = self.buffer.pop_front().unwrap();
+        let alpha = self.get_stepsize(self._count, &s);
+        let v = self.get_value(&s).unwrap_or(0.0);
+        self.update_value(&s, v + alpha * (G + bootstrap - v));
+        self.increment_count();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for NStepTD<S> {
+    fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    fn step(&mut self) {
+        let (s, r, s_next) = self.one_step.take().unwrap();
+        self.buffer.push_back((s, r));
+        self.last_state = s_next;
+        match self.last_state {
+            Some(_) => {
+                if self.buffer.len() >= self.n {
+                    self.update_oldest();
+                }
+            }
+            None => self.flush(),
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for NStepTD<S> {
+    fn start_episode(&mut self) {
+        self.clear_buffer();
+        self.reset_increment();
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, next_state: Option<&S>) {
+        self.update_one_step(state.clone(), reward, next_state.cloned());
+        self.step();
+    }
+
+    /// Truncated episodes still hold up to n-1 pending updates
+    fn finish_episode(&mut self) {
+        self.flush();
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  TD(λ) with Eligibility Traces
+// └──────────────────────────────────────────────────────────┘
+/// Eligibility trace update for the visited state (all traces first decay by γλ)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EligibilityTrace {
+    /// e(s) ← e(s) + 1
+    Accumulating,
+    /// e(s) ← 1
+    Replacing,
+    /// e(s) ← (1 - α)e(s) + 1
+    Dutch,
+}
+
+/// Backward-view TD(λ) prediction
+///
+/// Traces are cleared on a terminal transition; call `reset_traces` at the start of an
+/// episode that may follow a truncated one.
+pub struct TDLambda<S: Eq + std::hash::Hash + Clone> {
+    value_function: HashMap<S, f64>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    lambda: f64,
+    trace_kind: EligibilityTrace,
+    traces: HashMap<S, f64>,
+    one_step: Option<(S, f64, Option<S>)>,
+    _count: usize,
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TDLambda<S> {
+    pub fn new(
+        value_function: HashMap<S, f64>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+        lambda: f64,
+        trace_kind: EligibilityTrace,
+    ) -> Self {
+        TDLambda {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            lambda,
+            trace_kind,
+            traces: HashMap::new(),
+            one_step: None,
+            _count: 0,
+        }
+    }
+
+    pub fn get_value(&self, s: &S) -> Option<f64> {
+        self.value_function.get(s).cloned()
+    }
+
+    pub fn get_trace(&self, s: &S) -> f64 {
+        self.traces.get(s).cloned().unwrap_or(0.0)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+
+    pub fn update_value(&mut self, state: &S, value: f64) {
+        self.value_function.insert(state.clone(), value);
+    }
+
+    pub fn update_one_step(&mut self, s: S, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn reset_traces(&mut self) {
+        self.traces.clear();
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for TDLambda<S> {
+    fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    fn step(&mut self) {
+        let (s, r, s_next) = self.one_step.take().unwrap();
+        let v = self.get_value(&s).unwrap_or(0.0);
+        let delta = match &s_next {
+            Some(s_next) => r + self.gamma * self.get_value(s_next).unwrap_or(0.0) - v,
+            None => r - v,
+        };
+        let alpha = self.get_stepsize(self._count, &s);
+
+        // Decay all traces, then bump the visited one
+        let decay = self.gamma * self.lambda;
+        self.traces.values_mut().for_each(|e| *e *= decay);
+        let e = self.traces.entry(s).or_insert(0.0);
+        *e = match self.trace_kind {
+            EligibilityTrace::Accumulating => *e + 1f64,
+            EligibilityTrace::Replacing => 1f64,
+            EligibilityTrace::Dutch => (1f64 - alpha) * *e + 1f64,
+        };
+
+        for (x, e) in self.traces.iter() {
+            let v = self.value_function.entry(x.clone()).or_insert(0.0);
+            *v += alpha * delta * e;
+        }
+        self.increment_count();
+
+        if s_next.is_none() {
+            self.reset_traces();
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for TDLambda<S> {
+    fn start_episode(&mut self) {
+        self.reset_traces();
+        self.reset_increment();
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, next_state: Option<&S>) {
+        self.update_one_step(state.clone(), reward, next_state.cloned());
+        self.step();
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  True Online TD(λ)
+// └──────────────────────────────────────────────────────────┘
+/// True online TD(λ) prediction (tabular case, dutch traces)
+///
+/// Exactly reproduces the online λ-return algorithm. Traces and the stored previous value
+/// are cleared on a terminal transition or by `reset_traces`.
+pub struct TrueOnlineTDLambda<S: Eq + std::hash::Hash + Clone> {
+    value_function: HashMap<S, f64>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    lambda: f64,
+    traces: HashMap<S, f64>,
+    v_old: f64,
+    one_step: Option<(S, f64, Option<S>)>,
+    _count: usize,
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TrueOnlineTDLambda<S> {
+    pub fn new(
+        value_function: HashMap<S, f64>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+        lambda: f64,
+    ) -> Self {
+        TrueOnlineTDLambda {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            lambda,
+            traces: HashMap::new(),
+            v_old: 0f64,
+            one_step: None,
+            _count: 0,
+        }
+    }
+
+    pub fn get_value(&self, s: &S) -> Option<f64> {
+        self.value_function.get(s).cloned()
+    }
+
+    pub fn get_trace(&self, s: &S) -> f64 {
+        self.traces.get(s).cloned().unwrap_or(0.0)
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+
+    pub fn update_value(&mut self, state: &S, value: f64) {
+        self.value_function.insert(state.clone(), value);
+    }
+
+    pub fn update_one_step(&mut self, s: S, r: f64, s_next: Option<S>) {
+        self.one_step = Some((s, r, s_next));
+    }
+
+    pub fn increment_count(&mut self) {
+        self._count += 1;
+    }
+
+    pub fn reset_increment(&mut self) {
+        self._count = 1;
+    }
+
+    pub fn reset_traces(&mut self) {
+        self.traces.clear();
+        self.v_old = 0f64;
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for TrueOnlineTDLambda<S> {
+    fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        let (s, r, s_next) = self.one_step.take().unwrap();
+        let V = self.get_value(&s).unwrap_or(0.0);
+        let V_next = s_next
+            .as_ref()
+            .and_then(|s_next| self.get_value(s_next))
+            .unwrap_or(0.0);
+        let delta = r + self.gamma * V_next - V;
+        let alpha = self.get_stepsize(self._count, &s);
+
+        // z ← γλz + (1 - αγλ z(s)) x(s)
+        let decay = self.gamma * self.lambda;
+        let z_s = self.get_trace(&s);
+        self.traces.values_mut().for_each(|e| *e *= decay);
+        *self.traces.entry(s.clone()).or_insert(0.0) += 1f64 - alpha * decay * z_s;
+
+        // w ← w + α(δ + V - V_old)z - α(V - V_old)x(s)
+        for (x, e) in self.traces.iter() {
+            let v = self.value_function.entry(x.clone()).or_insert(0.0);
+            *v += alpha * (delta + V - self.v_old) * e;
+        }
+        let v = self.value_function.entry(s).or_insert(0.0);
+        *v -= alpha * (V - self.v_old);
+        self.v_old = V_next;
+        self.increment_count();
+
+        if s_next.is_none() {
+            self.reset_traces();
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for TrueOnlineTDLambda<S> {
+    fn start_episode(&mut self) {
+        self.reset_traces();
+        self.reset_increment();
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, next_state: Option<&S>) {
+        self.update_one_step(state.clone(), reward, next_state.cloned());
+        self.step();
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Offline λ-return
+// └──────────────────────────────────────────────────────────┘
+/// Offline λ-return algorithm
+///
+/// Uses the same `(S, f64)` episode format as the Monte Carlo predictors. An episode given
+/// through `update_episode` ends in a terminal state; one fed through `feed` and cut off by a
+/// step cap bootstraps its last return from the final next state. All λ-returns are computed
+/// from the value function at the start of the episode, then applied.
+pub struct OfflineLambdaReturn<S: Eq + std::hash::Hash + Clone> {
+    value_function: HashMap<S, f64>,
+    stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+    gamma: f64,
+    lambda: f64,
+    episode: Vec<(S, f64)>,
+    last_state: Option<S>,
+}
+
+impl<S: Eq + std::hash::Hash + Clone> OfflineLambdaReturn<S> {
+    pub fn new(
+        value_function: HashMap<S, f64>,
+        stepsize_scheduler: Box<dyn StepsizeScheduler<S>>,
+        gamma: f64,
+        lambda: f64,
+    ) -> Self {
+        OfflineLambdaReturn {
+            value_function,
+            stepsize_scheduler,
+            gamma,
+            lambda,
+            episode: Vec::new(),
+            last_state: None,
+        }
+    }
+
+    /// Set a terminal episode
+    pub fn update_episode(&mut self, episode: &[(S, f64)]) {
+        self.episode = episode.to_vec();
+        self.last_state = None;
+    }
+
+    pub fn get_value(&self, s: &S) -> Option<f64> {
+        self.value_function.get(s).cloned()
+    }
+
+    pub fn get_stepsize(&mut self, t: usize, s: &S) -> f64 {
+        self.stepsize_scheduler.stepsize(t, s)
+    }
+
+    pub fn update_value(&mut self, state: &S, value: f64) {
+        self.value_function.insert(state.clone(), value);
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> ValuePredictor<S> for OfflineLambdaReturn<S> {
+    fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        let l = self.episode.len();
+        if l == 0 {
+            panic!("Episode is empty");
+        }
+
+        let episode = self.episode.clone();
+
+        // Backward recursion: G_t = r_{t+1} + γ[(1-λ)V(s_{t+1}) + λG_{t+1}], with
+        // G_{T-1} = r_T at termination and r_T + γV(s_T) after truncation
+        let v_last = self
+            .last_state
+            .as_ref()
+            .map_or(0f64, |s| self.get_value(s).unwrap_or(0.0));
+        let mut G = vec![0f64; l];
+        G[l - 1] = episode[l - 1].1 + self.gamma * v_last;
+        for t in (0..l - 1).rev() {
+            let v_next = self.get_value(&episode[t + 1].0).unwrap_or(0.0);
+            G[t] = episode[t].1
+                + self.gamma * ((1f64 - self.lambda) * v_next + self.lambda * G[t + 1]);
+        }
+
+        // Apply the accumulated updates
+        let mut increments: HashMap<S, f64> = HashMap::new();
+        for (t, ((s, _), g)) in episode.iter().zip(G).enumerate() {
+            let v = self.get_value(s).unwrap_or(0.0);
+            let alpha = self.get_stepsize(t, s);
+            *increments.entry(s.clone()).or_insert(0.0) += alpha * (g - v);
+        }
+        for (s, dv) in increments {
+            let v = self.get_value(&s).unwrap_or(0.0);
+            self.update_value(&s, v + dv);
+        }
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone> TransitionPredictor<S> for OfflineLambdaReturn<S> {
+    fn start_episode(&mut self) {
+        self.episode.clear();
+        self.last_state = None;
+    }
+
+    fn feed(&mut self, state: &S, reward: f64, next_state: Option<&S>) {
+        self.episode.push((state.clone(), reward));
+        self.last_state = next_state.cloned();
+    }
+
+    fn finish_episode(&mut self) {
+        if !self.episode.is_empty() {
+            self.step();
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Off-policy Montecarlo
+// └──────────────────────────────────────────────────────────┘
+/// Off-policy every-visit Monte Carlo prediction of V^π
+///
+/// Episodes are `(S, A, R)` triples generated by the behavior policy b. Each return is weighted
+/// by ρ = Π π(A_k|S_k) / b(A_k|S_k) over the rest of the episode and averaged exactly
+/// according to `sampling`, so no step-size scheduler is involved.
+pub struct OffPolicyMC<S, A, P, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    P: Policy<S, A>,
+    B: Policy<S, A>,
+{
+    value_function: HashMap<S, f64>,
+    target_policy: P,
+    behavior_policy: B,
+    gamma: f64,
+    sampling: ImportanceSampling,
+    weights: HashMap<S, f64>,
+    episode: Vec<(S, A, f64)>,
+}
+
+impl<S, A, P, B> OffPolicyMC<S, A, P, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    P: Policy<S, A>,
+    B: Policy<S, A>,
+{
+    pub fn new(
+        value_function: HashMap<S, f64>,
+        target_policy: P,
+        behavior_policy: B,
+        gamma: f64,
+        sampling: ImportanceSampling,
+    ) -> Self {
+        OffPolicyMC {
+            value_function,
+            target_policy,
+            behavior_policy,
+            gamma,
+            sampling,
+            weights: HashMap::new(),
+            episode: Vec::new(),
+        }
+    }
+
+    pub fn update_episode(&mut self, episode: &[(S, A, f64)]) {
+        self.episode = episode.to_vec()
+    }
+
+    pub fn get_target_policy(&self) -> &P {
+        &self.target_policy
+    }
+
+    pub fn get_behavior_policy(&self) -> &B {
+        &self.behavior_policy
+    }
+
+    pub fn get_value(&self, s: &S) -> Option<f64> {
+        self.value_function.get(s).cloned()
+    }
+
+    pub fn update_value(&mut self, state: &S, value: f64) {
+        self.value_function.insert(state.clone(), value);
+    }
+
+    /// Visit count N(s) (ordinary) or cumulative weight C(s) (weighted)
+    pub fn get_weight(&self, s: &S) -> f64 {
+        self.weights.get(s).cloned().unwrap_or(0.0)
+    }
+}
+
+impl<S, A, P, B> ValuePredictor<S> for OffPolicyMC<S, A, P, B>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone + PartialEq,
+    P: Policy<S, A>,
+    B: Policy<S, A>,
+{
+    fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    #[allow(non_snake_case)]
+    fn step(&mut self) {
+        if self.episode.is_empty() {
+            panic!("Episode is empty");
+        }
+
+        let episode = self.episode.clone();
+
+        // Backward pass accumulating return and importance ratio
+        let mut G = 0f64;
+        let mut W = 1f64;
+        for (s, a, r) in episode.iter().rev() {
+            G = self.gamma * G + r;
+            W *= self.target_policy.prob(s, a) / self.behavior_policy.prob(s, a);
+            let v = self.get_value(s).unwrap_or(0.0);
+            let c = self.weights.entry(s.clone()).or_insert(0.0);
+            match self.sampling {
+                ImportanceSampling::Ordinary => {
+                    *c += 1f64;
+                    let c = *c;
+                    self.update_value(s, v + (W * G - v) / c);
+                }
+                ImportanceSampling::Weighted => {
+                    // Every earlier ratio contains this zero factor as well
+                    if W == 0f64 {
+                        break;
+                    }
+                    *c += W;
+                    let c = *c;
+                    self.update_value(s, v + W / c * (G - v));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::policy::TabularPolicy;
+    use crate::learning::util::ConstantStepsize;
+    use peroxide::fuga::*;
+
+    fn every_visit(gamma: f64, episode: &[(&'static str, f64)]) -> EveryvisitMC<&'static str> {
+        let mut mc = EveryvisitMC::new(HashMap::new(), Box::new(ConstantStepsize::new(1.0)), gamma);
+        mc.update_episode(episode);
+        mc.step();
+        mc
+    }
+
+    fn first_visit(gamma: f64, episode: &[(&'static str, f64)]) -> FirstvisitMC<&'static str> {
+        let mut mc = FirstvisitMC::new(HashMap::new(), Box::new(ConstantStepsize::new(1.0)), gamma);
+        mc.update_episode(episode);
+        mc.step();
+        mc
+    }
+
+    #[test]
+    fn mc_pairs_each_state_with_its_own_return() {
+        // G_0 = 1 + 0.5 * 4 = 3, G_1 = 4
+        let episode = [("a", 1.0), ("b", 4.0)];
+        let every = every_visit(0.5, &episode);
+        let first = first_visit(0.5, &episode);
+        for mc in [every.get_value_function(), first.get_value_function()] {
+            assert_eq!(mc[&"a"], 3.0);
+            assert_eq!(mc[&"b"], 4.0);
+        }
+    }
+
+    #[test]
+    fn every_visit_updates_repeated_states_in_order() {
+        // G_0 = 2, G_1 = 1: every-visit ends at the later return, first-visit keeps the first
+        let episode = [("a", 1.0), ("a", 1.0)];
+        assert_eq!(every_visit(1.0, &episode).get_value(&"a"), Some(1.0));
+        let first = first_visit(1.0, &episode);
+        assert_eq!(first.get_value(&"a"), Some(2.0));
+        assert_eq!(first.get_visit_count(&"a"), 1);
+    }
+
+    /// Behavior policy: a fair coin between actions 0 and 1
+    struct Coin;
+
+    impl Policy<&'static str, usize> for Coin {
+        fn gen_action(&self, _state: &&'static str, rng: &mut dyn RngCore) -> Option<usize> {
+            Some(rng.gen_range(0..2))
+        }
+
+        fn action_probs(&self, _state: &&'static str) -> Vec<(usize, f64)> {
+            vec![(0, 0.5), (1, 0.5)]
+        }
+    }
+
+    fn off_policy(
+        sampling: ImportanceSampling,
+    ) -> OffPolicyMC<&'static str, usize, TabularPolicy<&'static str, usize>, Coin> {
+        let target = TabularPolicy::new(HashMap::from([("a", 1), ("b", 1)]));
+        OffPolicyMC::new(HashMap::new(), target, Coin, 1.0, sampling)
+    }
+
+    #[test]
+    fn off_policy_mc_importance_sampling() {
+        // ρ = 2 per step taking the target action: V(b) = ρ_b G_b, V(a) = ρ_a ρ_b G_a
+        let followed = [("a", 1, 1.0), ("b", 1, 2.0)];
+        let strayed = [("a", 0, 1.0), ("b", 1, 2.0)];
+
+        let mut ordinary = off_policy(ImportanceSampling::Ordinary);
+        ordinary.update_episode(&followed);
+        ordinary.step();
+        assert_eq!(ordinary.get_value(&"b"), Some(4.0));
+        assert_eq!(ordinary.get_value(&"a"), Some(12.0));
+        ordinary.update_episode(&strayed);
+        ordinary.step();
+        assert_eq!(ordinary.get_value(&"a"), Some(6.0));
+        assert_eq!(ordinary.get_weight(&"a"), 2.0);
+
+        let mut weighted = off_policy(ImportanceSampling::Weighted);
+        weighted.update_episode(&followed);
+        weighted.step();
+        assert_eq!(weighted.get_value(&"b"), Some(2.0));
+        assert_eq!(weighted.get_value(&"a"), Some(3.0));
+        weighted.update_episode(&strayed);
+        weighted.step();
+        assert_eq!(weighted.get_value(&"a"), Some(3.0));
+        assert_eq!(weighted.get_weight(&"a"), 4.0);
+        assert_eq!(weighted.get_weight(&"b"), 4.0);
+    }
+
+    type Transition = (&'static str, f64, Option<&'static str>);
+
+    /// a -> b -> c -> terminal with rewards 1, 2, 3
+    const CHAIN: [Transition; 3] = [
+        ("a", 1.0, Some("b")),
+        ("b", 2.0, Some("c")),
+        ("c", 3.0, None),
+    ];
+
+    fn chain_values() -> HashMap<&'static str, f64> {
+        HashMap::from([("a", 0.5), ("b", -1.0), ("c", 2.0)])
+    }
+
+    fn run<P: TransitionPredictor<&'static str>>(predictor: &mut P, episode: &[Transition]) {
+        predictor.start_episode();
+        for (s, r, s_next) in episode {
+            predictor.feed(s, *r, s_next.as_ref());
+        }
+        predictor.finish_episode();
+    }
+
+    fn assert_same_values(a: &HashMap<&'static str, f64>, b: &HashMap<&'static str, f64>) {
+        assert_eq!(a.len(), b.len());
+        for (s, v) in a {
+            assert!((v - b[s]).abs() < 1e-12, "V({}) = {} vs {}", s, v, b[s]);
+        }
+    }
+
+    #[test]
+    fn one_step_td_is_td0() {
+        let step = || Box::new(ConstantStepsize::new(0.5));
+        let mut n_step = NStepTD::new(chain_values(), step(), 0.9, 1);
+        let mut td0 = TD0::new(chain_values(), step(), 0.9);
+        for _ in 0..3 {
+            run(&mut n_step, &CHAIN);
+            run(&mut td0, &CHAIN);
+            assert_same_values(n_step.get_value_function(), td0.get_value_function());
+        }
+    }
+
+    #[test]
+    fn long_n_step_td_is_every_visit_mc() {
+        // The whole episode is flushed at termination, so every return is a full MC return
+        let step = || Box::new(ConstantStepsize::new(0.5));
+        for n in [3, 10] {
+            let mut n_step = NStepTD::new(chain_values(), step(), 0.9, n);
+            let mut mc = EveryvisitMC::new(chain_values(), step(), 0.9);
+            for _ in 0..3 {
+                run(&mut n_step, &CHAIN);
+                run(&mut mc, &CHAIN);
+                assert_same_values(n_step.get_value_function(), mc.get_value_function());
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_n_step_td_bootstraps_from_the_last_state() {
+        let mut n_step = NStepTD::new(chain_values(), Box::new(ConstantStepsize::new(1.0)), 0.5, 3);
+        run(&mut n_step, &CHAIN[..2]);
+        // G(a) = 1 + 0.5 * 2 + 0.25 V(c), G(b) = 2 + 0.5 V(c)
+        assert_eq!(n_step.get_value(&"a"), Some(2.5));
+        assert_eq!(n_step.get_value(&"b"), Some(3.0));
+        assert_eq!(n_step.get_value(&"c"), Some(2.0));
+    }
+
+    #[test]
+    fn truncated_lambda_return_bootstraps_from_the_last_state() {
+        let step = || Box::new(ConstantStepsize::new(1.0));
+        let mut truncated = OfflineLambdaReturn::new(chain_values(), step(), 0.5, 0.5);
+        run(&mut truncated, &CHAIN[..2]);
+        // G_1 = 2 + 0.5 V(c) = 3, G_0 = 1 + 0.5 (0.5 V(b) + 0.5 G_1) = 1.5
+        assert_eq!(truncated.get_value(&"b"), Some(3.0));
+        assert_eq!(truncated.get_value(&"a"), Some(1.5));
+
+        // A terminal episode does not bootstrap
+        let mut terminal = OfflineLambdaReturn::new(chain_values(), step(), 0.5, 0.5);
+        run(&mut terminal, &CHAIN);
+        assert_eq!(terminal.get_value(&"c"), Some(3.0));
+    }
+
+    fn td_lambda(kind: EligibilityTrace, lambda: f64) -> TDLambda<&'static str> {
+        TDLambda::new(
+            chain_values(),
+            Box::new(ConstantStepsize::new(0.5)),
+            0.9,
+            lambda,
+            kind,
+        )
+    }
+
+    #[test]
+    fn traces_decay_by_gamma_lambda() {
+        let decay = 0.9 * 0.8;
+        for kind in [
+            EligibilityTrace::Accumulating,
+            EligibilityTrace::Replacing,
+            EligibilityTrace::Dutch,
+        ] {
+            let mut td = td_lambda(kind, 0.8);
+            td.start_episode();
+            td.feed(&"a", 1.0, Some(&"b"));
+            assert_eq!(td.get_trace(&"a"), 1.0);
+            td.feed(&"b", 2.0, Some(&"c"));
+            assert!((td.get_trace(&"a") - decay).abs() < 1e-12, "{:?}", kind);
+            assert_eq!(td.get_trace(&"b"), 1.0);
+        }
+
+        let mut td = TrueOnlineTDLambda::new(
+            chain_values(),
+            Box::new(ConstantStepsize::new(0.5)),
+            0.9,
+            0.8,
+        );
+        td.start_episode();
+        td.feed(&"a", 1.0, Some(&"b"));
+        assert_eq!(td.get_trace(&"a"), 1.0);
+        td.feed(&"b", 2.0, Some(&"c"));
+        assert!((td.get_trace(&"a") - decay).abs() < 1e-12);
+        assert_eq!(td.get_trace(&"b"), 1.0);
+    }
+
+    #[test]
+    fn revisits_follow_the_trace_kind() {
+        // e(a) = γλ before the second bump
+        let decay = 0.9 * 0.8;
+        let revisit = |kind| {
+            let mut td = td_lambda(kind, 0.8);
+            td.start_episode();
+            td.feed(&"a", 0.0, Some(&"a"));
+            td.feed(&"a", 0.0, Some(&"a"));
+            td.get_trace(&"a")
+        };
+        assert!((revisit(EligibilityTrace::Accumulating) - (decay + 1.0)).abs() < 1e-12);
+        assert_eq!(revisit(EligibilityTrace::Replacing), 1.0);
+        assert!((revisit(EligibilityTrace::Dutch) - (0.5 * decay + 1.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn traces_reset_at_episode_boundaries() {
+        let mut td = td_lambda(EligibilityTrace::Accumulating, 0.8);
+        let mut true_online = TrueOnlineTDLambda::new(
+            chain_values(),
+            Box::new(ConstantStepsize::new(0.5)),
+            0.9,
+            0.8,
+        );
+
+        // Terminal transitions clear the traces
+        run(&mut td, &CHAIN);
+        run(&mut true_online, &CHAIN);
+        for s in ["a", "b", "c"] {
+            assert_eq!(td.get_trace(&s), 0.0);
+            assert_eq!(true_online.get_trace(&s), 0.0);
+        }
+
+        // Truncated episodes keep them until the next start
+        run(&mut td, &CHAIN[..2]);
+        run(&mut true_online, &CHAIN[..2]);
+        assert!(td.get_trace(&"a") > 0.0);
+        assert!(true_online.get_trace(&"a") > 0.0);
+        td.start_episode();
+        true_online.start_episode();
+        for s in ["a", "b", "c"] {
+            assert_eq!(td.get_trace(&s), 0.0);
+            assert_eq!(true_online.get_trace(&s), 0.0);
+        }
+    }
+
+    #[test]
+    fn td_lambda_zero_is_td0() {
+        for kind in [
+            EligibilityTrace::Accumulating,
+            EligibilityTrace::Replacing,
+            EligibilityTrace::Dutch,
+        ] {
+            let mut td = td_lambda(kind, 0.0);
+            let mut td0 = TD0::new(chain_values(), Box::new(ConstantStepsize::new(0.5)), 0.9);
+            for _ in 0..3 {
+                run(&mut td, &CHAIN);
+                run(&mut td0, &CHAIN);
+            }
+            assert_same_values(td.get_value_function(), td0.get_value_function());
+        }
+    }
+
+    #[test]
+    fn true_online_matches_offline_lambda_return_on_single_steps() {
+        let step = || Box::new(ConstantStepsize::new(0.01));
+        let mut true_online = TrueOnlineTDLambda::new(chain_values(), step(), 0.9, 0.8);
+        let mut offline = OfflineLambdaReturn::new(chain_values(), step(), 0.9, 0.8);
+        for episode in [[("a", 1.0, None)], [("b", -2.0, None)], [("a", 3.0, None)]] {
+            run(&mut true_online, &episode);
+            run(&mut offline, &episode);
+            assert_same_values(
+                true_online.get_value_function(),
+                offline.get_value_function(),
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod base;
 pub mod env;
 pub mod learning;
+pub mod planning;
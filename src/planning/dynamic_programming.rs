@@ -0,0 +1,370 @@
+use crate::base::policy::{Policy, TabularPolicy};
+use crate::base::process::MarkovDecisionProcess;
+use std::collections::HashMap;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Planning Result
+// └──────────────────────────────────────────────────────────┘
+/// Output of a dynamic programming solver
+///
+/// `residuals[k]` is the largest value change during the k-th sweep.
+#[derive(Debug, Clone)]
+pub struct PlanningResult<S: Eq + std::hash::Hash + Clone, A: Clone> {
+    value_function: HashMap<S, f64>,
+    policy: TabularPolicy<S, A>,
+    residuals: Vec<f64>,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone> PlanningResult<S, A> {
+    pub fn get_value_function(&self) -> &HashMap<S, f64> {
+        &self.value_function
+    }
+
+    pub fn get_policy(&self) -> &TabularPolicy<S, A> {
+        &self.policy
+    }
+
+    pub fn get_residuals(&self) -> &[f64] {
+        &self.residuals
+    }
+
+    pub fn into_parts(self) -> (HashMap<S, f64>, TabularPolicy<S, A>, Vec<f64>) {
+        (self.value_function, self.policy, self.residuals)
+    }
+}
+
+/// One-step lookahead: q(s, a) = Σ p(s', r | s, a) [r + γ V(s')]
+///
+/// Terminal outcomes (`None`) contribute only their reward.
+pub fn action_value<S, A, M>(
+    mdp: &M,
+    value_function: &HashMap<S, f64>,
+    state: &S,
+    action: &A,
+    gamma: f64,
+) -> f64
+where
+    S: Eq + std::hash::Hash + Clone,
+    M: MarkovDecisionProcess<S, A>,
+{
+    mdp.transition_probs(state, action)
+        .into_iter()
+        .map(|(s_next, p, r)| {
+            let v_next = s_next
+                .and_then(|s| value_function.get(&s).cloned())
+                .unwrap_or(0.0);
+            p * (r + gamma * v_next)
+        })
+        .sum()
+}
+
+/// Greedy policy with respect to a value function (first maximizing action wins)
+pub fn greedy_policy<S, A, M>(
+    mdp: &M,
+    value_function: &HashMap<S, f64>,
+    gamma: f64,
+) -> TabularPolicy<S, A>
+where
+    S: Eq + std::hash::Hash + Clone,
+    A: Clone,
+    M: MarkovDecisionProcess<S, A>,
+{
+    let mut policy = HashMap::new();
+    for s in mdp.states() {
+        if let Some((a, _)) = best_action(mdp, value_function, &s, gamma) {
+            policy.insert(s, a);
+        }
+    }
+    TabularPolicy::new(policy)
+}
+
+fn best_action<S, A, M>(
+    mdp: &M,
+    value_function: &HashMap<S, f64>,
+    state: &S,
+    gamma: f64,
+) -> Option<(A, f64)>
+where
+    S: Eq + std::hash::Hash + Clone,
+    M: MarkovDecisionProcess<S, A>,
+{
+    let mut best: Option<(A, f64)> = None;
+    for a in mdp.actions_at(state) {
+        let q = action_value(mdp, value_function, state, &a, gamma);
+        match best {
+            Some((_, q_best)) if q <= q_best => {}
+            _ => best = Some((a, q)),
+        }
+    }
+    best
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Iterative Policy Evaluation
+// └──────────────────────────────────────────────────────────┘
+/// Iterative policy evaluation (in-place sweeps)
+///
+/// Backups are weighted by `action_probs`, so stochastic policies (ε-greedy, uniform random)
+/// are evaluated exactly. Stops when the largest change in a sweep drops below `tol` or after
+/// `max_sweeps` sweeps.
+#[derive(Debug, Clone)]
+pub struct PolicyEvaluation {
+    gamma: f64,
+    tol: f64,
+    max_sweeps: usize,
+}
+
+impl PolicyEvaluation {
+    pub fn new(gamma: f64, tol: f64, max_sweeps: usize) -> Self {
+        PolicyEvaluation {
+            gamma,
+            tol,
+            max_sweeps,
+        }
+    }
+
+    pub fn evaluate<S, A, M, P>(&self, mdp: &M, policy: &P) -> PlanningResult<S, A>
+    where
+        S: Eq + std::hash::Hash + Clone,
+        A: Clone,
+        M: MarkovDecisionProcess<S, A>,
+        P: Policy<S, A>,
+    {
+        let mut value_function: HashMap<S, f64> =
+            mdp.states().into_iter().map(|s| (s, 0f64)).collect();
+        let residuals =
+            self.sweep_until_converged(mdp, policy, &mut value_function, self.max_sweeps);
+        let policy = greedy_policy(mdp, &value_function, self.gamma);
+        PlanningResult {
+            value_function,
+            policy,
+            residuals,
+        }
+    }
+
+    fn sweep_until_converged<S, A, M, P>(
+        &self,
+        mdp: &M,
+        policy: &P,
+        value_function: &mut HashMap<S, f64>,
+        max_sweeps: usize,
+    ) -> Vec<f64>
+    where
+        S: Eq + std::hash::Hash + Clone,
+        A: Clone,
+        M: MarkovDecisionProcess<S, A>,
+        P: Policy<S, A>,
+    {
+        let states = mdp.states();
+        let mut residuals = vec![];
+        for _ in 0..max_sweeps {
+            let mut delta = 0f64;
+            for s in states.iter() {
+                let new_v = policy
+                    .action_probs(s)
+                    .iter()
+                    .filter(|(_, p)| *p > 0.0)
+                    .map(|(a, p)| p * action_value(mdp, value_function, s, a, self.gamma))
+                    .sum();
+                let v = value_function.insert(s.clone(), new_v).unwrap_or(0.0);
+                delta = delta.max((new_v - v).abs());
+            }
+            residuals.push(delta);
+            if delta < self.tol {
+                break;
+            }
+        }
+        residuals
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Policy Iteration
+// └──────────────────────────────────────────────────────────┘
+/// Policy iteration
+///
+/// Alternates policy evaluation and greedy improvement until the policy is stable.
+/// `max_sweeps` bounds the total number of evaluation sweeps over all iterations.
+#[derive(Debug, Clone)]
+pub struct PolicyIteration {
+    evaluation: PolicyEvaluation,
+}
+
+impl PolicyIteration {
+    pub fn new(gamma: f64, tol: f64, max_sweeps: usize) -> Self {
+        PolicyIteration {
+            evaluation: PolicyEvaluation::new(gamma, tol, max_sweeps),
+        }
+    }
+
+    pub fn solve<S, A, M>(&self, mdp: &M) -> PlanningResult<S, A>
+    where
+        S: Eq + std::hash::Hash + Clone,
+        A: Clone,
+        M: MarkovDecisionProcess<S, A>,
+    {
+        let gamma = self.evaluation.gamma;
+        let states = mdp.states();
+        let mut value_function: HashMap<S, f64> =
+            states.iter().map(|s| (s.clone(), 0f64)).collect();
+
+        // Arbitrary initial policy: first available action
+        let mut policy = TabularPolicy::new(
+            states
+                .iter()
+                .filter_map(|s| mdp.actions_at(s).into_iter().next().map(|a| (s.clone(), a)))
+                .collect(),
+        );
+
+        let mut residuals = vec![];
+        while residuals.len() < self.evaluation.max_sweeps {
+            let budget = self.evaluation.max_sweeps - residuals.len();
+            residuals.extend(self.evaluation.sweep_until_converged(
+                mdp,
+                &policy,
+                &mut value_function,
+                budget,
+            ));
+
+            // Improve: switch only on an improvement beyond `tol`, so that floating-point
+            // noise between equally good actions cannot make the policy cycle
+            let mut stable = true;
+            for s in states.iter() {
+                let Some(old_action) = policy.get_action(s).cloned() else {
+                    continue;
+                };
+                let q_old = action_value(mdp, &value_function, s, &old_action, gamma);
+                if let Some((a, q)) = best_action(mdp, &value_function, s, gamma) {
+                    if q > q_old + self.evaluation.tol {
+                        policy.update_action(s, a);
+                        stable = false;
+                    }
+                }
+            }
+            if stable {
+                break;
+            }
+        }
+
+        PlanningResult {
+            value_function,
+            policy,
+            residuals,
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Value Iteration
+// └──────────────────────────────────────────────────────────┘
+/// Value iteration (in-place sweeps of the Bellman optimality backup)
+#[derive(Debug, Clone)]
+pub struct ValueIteration {
+    gamma: f64,
+    tol: f64,
+    max_sweeps: usize,
+}
+
+impl ValueIteration {
+    pub fn new(gamma: f64, tol: f64, max_sweeps: usize) -> Self {
+        ValueIteration {
+            gamma,
+            tol,
+            max_sweeps,
+        }
+    }
+
+    pub fn solve<S, A, M>(&self, mdp: &M) -> PlanningResult<S, A>
+    where
+        S: Eq + std::hash::Hash + Clone,
+        A: Clone,
+        M: MarkovDecisionProcess<S, A>,
+    {
+        let states = mdp.states();
+        let mut value_function: HashMap<S, f64> =
+            states.iter().map(|s| (s.clone(), 0f64)).collect();
+
+        let mut residuals = vec![];
+        for _ in 0..self.max_sweeps {
+            let mut delta = 0f64;
+            for s in states.iter() {
+                let new_v = best_action(mdp, &value_function, s, self.gamma)
+                    .map(|(_, q)| q)
+                    .unwrap_or(0f64);
+                let v = value_function.insert(s.clone(), new_v).unwrap_or(0.0);
+                delta = delta.max((new_v - v).abs());
+            }
+            residuals.push(delta);
+            if delta < self.tol {
+                break;
+            }
+        }
+
+        let policy = greedy_policy(mdp, &value_function, self.gamma);
+        PlanningResult {
+            value_function,
+            policy,
+            residuals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::policy::UniformRandomPolicy;
+    use crate::env::grid_world::{GridCell, GridWorld, RewardSpec};
+
+    /// Sutton & Barto Example 4.1: 4x4 grid, terminal corners, -1 per step
+    fn small_grid_world() -> GridWorld {
+        let mut env = GridWorld::with_walls(4, 4, (1, 1), (0, 0), vec![]);
+        env.set_cell(&(3, 3), GridCell::Goal);
+        env.set_reward_spec(RewardSpec::new(-1.0, -1.0, -1.0, -1.0));
+        env
+    }
+
+    #[test]
+    fn evaluates_the_uniform_random_policy() {
+        let env = small_grid_world();
+        let result = PolicyEvaluation::new(1.0, 1e-10, 10000)
+            .evaluate(&env, &UniformRandomPolicy::new(&env));
+        // Figure 4.1, k = ∞; row y, column x
+        let expected = [
+            [0.0, -14.0, -20.0, -22.0],
+            [-14.0, -18.0, -20.0, -20.0],
+            [-20.0, -20.0, -18.0, -14.0],
+            [-22.0, -20.0, -14.0, 0.0],
+        ];
+        for (s, v) in result.get_value_function() {
+            assert!((v - expected[s.1][s.0]).abs() < 1e-6, "V{:?} = {}", s, v);
+        }
+        assert_eq!(result.get_value_function().len(), 14);
+    }
+
+    #[test]
+    fn policy_and_value_iteration_agree_on_the_optimum() {
+        // γ < 1: the initial policy (always the first action) never terminates
+        let env = small_grid_world();
+        let gamma = 0.9;
+        let pi = PolicyIteration::new(gamma, 1e-10, 10000).solve(&env);
+        let vi = ValueIteration::new(gamma, 1e-10, 10000).solve(&env);
+        assert!(
+            pi.get_residuals().len() < 10000,
+            "policy iteration did not settle"
+        );
+        for (s, v) in vi.get_value_function() {
+            // d steps to the nearest corner: -(1 + γ + ... + γ^(d-1))
+            let d = (s.0 + s.1).min(6 - s.0 - s.1) as i32;
+            let optimal = -(1.0 - gamma.powi(d)) / (1.0 - gamma);
+            assert!((v - optimal).abs() < 1e-6, "V_VI{:?} = {}", s, v);
+            assert!((pi.get_value_function()[s] - optimal).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn cliff_walking_takes_the_edge_path() {
+        // Up, 11 steps right along the cliff edge, down into the goal
+        let vi = ValueIteration::new(1.0, 1e-10, 1000).solve(&GridWorld::cliff_walking());
+        assert!((vi.get_value_function()[&(0, 0)] + 13.0).abs() < 1e-9);
+    }
+}
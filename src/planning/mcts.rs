@@ -0,0 +1,419 @@
+use crate::base::agent::Agent;
+use crate::base::policy::Policy;
+use crate::base::process::MarkovDecisionProcess;
+use peroxide::fuga::*;
+use std::marker::PhantomData;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Monte Carlo Tree Search
+// └──────────────────────────────────────────────────────────┘
+/// UCT over any `MarkovDecisionProcess` used as a generative model (`sample_step`)
+///
+/// Each simulation descends the tree choosing untried actions first and then maximizing
+/// Q(s, a) + c √(ln N(s) / N(s, a)), adds one new state node, and evaluates it with a
+/// rollout of `rollout_policy`. Stochastic outcomes get separate child nodes. Simulations
+/// stop after `max_depth` steps from the root; returns are discounted by `gamma`.
+pub struct MCTS<S, A, P: Policy<S, A>> {
+    rollout_policy: P,
+    exploration: f64,
+    num_simulations: usize,
+    max_depth: usize,
+    gamma: f64,
+    state_type: PhantomData<S>,
+    action_type: PhantomData<A>,
+}
+
+/// Root statistics of a search
+#[derive(Debug, Clone)]
+pub struct SearchResult<A> {
+    best_action: Option<A>,
+    action_stats: Vec<(A, usize, f64)>,
+}
+
+impl<A> SearchResult<A> {
+    /// Most visited root action
+    pub fn get_best_action(&self) -> Option<&A> {
+        self.best_action.as_ref()
+    }
+
+    /// `(action, visit count, mean return)` for every root action
+    pub fn get_action_stats(&self) -> &[(A, usize, f64)] {
+        &self.action_stats
+    }
+
+    pub fn into_best_action(self) -> Option<A> {
+        self.best_action
+    }
+}
+
+struct Node<S, A> {
+    state: S,
+    visits: usize,
+    edges: Vec<Edge<S, A>>,
+}
+
+struct Edge<S, A> {
+    action: A,
+    visits: usize,
+    value_sum: f64,
+    children: Vec<(S, usize)>,
+}
+
+impl<S: Clone + PartialEq, A: Clone, P: Policy<S, A>> MCTS<S, A, P> {
+    pub fn new(
+        rollout_policy: P,
+        exploration: f64,
+        num_simulations: usize,
+        max_depth: usize,
+        gamma: f64,
+    ) -> Self {
+        MCTS {
+            rollout_policy,
+            exploration,
+            num_simulations,
+            max_depth,
+            gamma,
+            state_type: PhantomData,
+            action_type: PhantomData,
+        }
+    }
+
+    pub fn get_rollout_policy(&self) -> &P {
+        &self.rollout_policy
+    }
+
+    /// Run `num_simulations` simulations from `root`
+    pub fn search<M: MarkovDecisionProcess<S, A>>(
+        &self,
+        mdp: &M,
+        root: &S,
+        rng: &mut dyn RngCore,
+    ) -> SearchResult<A> {
+        let mut tree = vec![self.new_node(mdp, root.clone())];
+        for _ in 0..self.num_simulations {
+            self.simulate(mdp, &mut tree, 0, 0, rng);
+        }
+
+        let action_stats = tree[0]
+            .edges
+            .iter()
+            .map(|e| {
+                let mean = if e.visits == 0 {
+                    0f64
+                } else {
+                    e.value_sum / e.visits as f64
+                };
+                (e.action.clone(), e.visits, mean)
+            })
+            .collect::<Vec<_>>();
+        // Most visited; ties go to the first action
+        let best_action = action_stats
+            .iter()
+            .fold(None, |best: Option<&(A, usize, f64)>, x| match best {
+                Some(b) if b.1 >= x.1 => Some(b),
+                _ => Some(x),
+            })
+            .map(|(a, _, _)| a.clone());
+        SearchResult {
+            best_action,
+            action_stats,
+        }
+    }
+
+    fn new_node<M: MarkovDecisionProcess<S, A>>(&self, mdp: &M, state: S) -> Node<S, A> {
+        let edges = mdp
+            .actions_at(&state)
+            .into_iter()
+            .map(|action| Edge {
+                action,
+                visits: 0,
+                value_sum: 0f64,
+                children: vec![],
+            })
+            .collect();
+        Node {
+            state,
+            visits: 0,
+            edges,
+        }
+    }
+
+    /// UCB1 choice among the edges of `node` (untried edges first, in order)
+    fn select_edge(&self, node: &Node<S, A>) -> Option<usize> {
+        if let Some(i) = node.edges.iter().position(|e| e.visits == 0) {
+            return Some(i);
+        }
+        let ln_n = (node.visits.max(1) as f64).ln();
+        node.edges
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let n = e.visits as f64;
+                (i, e.value_sum / n + self.exploration * (ln_n / n).sqrt())
+            })
+            .fold(None, |best: Option<(usize, f64)>, (i, u)| match best {
+                Some((_, b)) if b >= u => best,
+                _ => Some((i, u)),
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Return of one simulation from `tree[idx]` at `depth`
+    fn simulate<M: MarkovDecisionProcess<S, A>>(
+        &self,
+        mdp: &M,
+        tree: &mut Vec<Node<S, A>>,
+        idx: usize,
+        depth: usize,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        if depth >= self.max_depth {
+            return 0f64;
+        }
+        // Leaf: evaluate by rollout
+        if idx != 0 && tree[idx].visits == 0 {
+            tree[idx].visits = 1;
+            let state = tree[idx].state.clone();
+            return self.rollout(mdp, state, depth, rng);
+        }
+        let Some(e) = self.select_edge(&tree[idx]) else {
+            tree[idx].visits += 1;
+            return 0f64;
+        };
+
+        let state = tree[idx].state.clone();
+        let action = tree[idx].edges[e].action.clone();
+        let (s_next, r) = mdp.sample_step(&state, &action, rng);
+        let g = match s_next {
+            Some(s_next) => {
+                let child = match tree[idx].edges[e]
+                    .children
+                    .iter()
+                    .find(|(s, _)| *s == s_next)
+                {
+                    Some((_, c)) => *c,
+                    None => {
+                        let node = self.new_node(mdp, s_next.clone());
+                        tree.push(node);
+                        let c = tree.len() - 1;
+                        tree[idx].edges[e].children.push((s_next, c));
+                        c
+                    }
+                };
+                r + self.gamma * self.simulate(mdp, tree, child, depth + 1, rng)
+            }
+            None => r,
+        };
+
+        let node = &mut tree[idx];
+        node.visits += 1;
+        node.edges[e].visits += 1;
+        node.edges[e].value_sum += g;
+        g
+    }
+
+    /// Discounted return of following `rollout_policy` from `state` until `max_depth`
+    fn rollout<M: MarkovDecisionProcess<S, A>>(
+        &self,
+        mdp: &M,
+        state: S,
+        depth: usize,
+        rng: &mut dyn RngCore,
+    ) -> f64 {
+        let mut g = 0f64;
+        let mut discount = 1f64;
+        let mut state = state;
+        for _ in depth..self.max_depth {
+            let Some(action) = self.rollout_policy.gen_action(&state, rng) else {
+                break;
+            };
+            let (s_next, r) = mdp.sample_step(&state, &action, rng);
+            g += discount * r;
+            discount *= self.gamma;
+            match s_next {
+                Some(s) => state = s,
+                None => break,
+            }
+        }
+        g
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  MCTS Agent
+// └──────────────────────────────────────────────────────────┘
+/// Decision-time planning agent: a fresh search from every visited state
+///
+/// Nothing is learned across steps, so `observe` and `set_greedy` do nothing.
+pub struct MCTSAgent<'a, S, A, M, P>
+where
+    M: MarkovDecisionProcess<S, A>,
+    P: Policy<S, A>,
+{
+    mdp: &'a M,
+    planner: MCTS<S, A, P>,
+    last_result: Option<SearchResult<A>>,
+}
+
+impl<'a, S, A, M, P> MCTSAgent<'a, S, A, M, P>
+where
+    S: Clone + PartialEq,
+    A: Clone,
+    M: MarkovDecisionProcess<S, A>,
+    P: Policy<S, A>,
+{
+    pub fn new(mdp: &'a M, planner: MCTS<S, A, P>) -> Self {
+        MCTSAgent {
+            mdp,
+            planner,
+            last_result: None,
+        }
+    }
+
+    pub fn get_planner(&self) -> &MCTS<S, A, P> {
+        &self.planner
+    }
+
+    /// Statistics of the most recent search
+    pub fn get_last_result(&self) -> Option<&SearchResult<A>> {
+        self.last_result.as_ref()
+    }
+}
+
+impl<'a, S, A, M, P> Agent<S, A> for MCTSAgent<'a, S, A, M, P>
+where
+    S: Clone + PartialEq,
+    A: Clone,
+    M: MarkovDecisionProcess<S, A>,
+    P: Policy<S, A>,
+{
+    fn select_action(&mut self, state: &S, rng: &mut dyn RngCore) -> Option<A> {
+        let result = self.planner.search(self.mdp, state, rng);
+        let action = result.get_best_action().cloned();
+        self.last_result = Some(result);
+        action
+    }
+
+    fn observe(
+        &mut self,
+        _state: &S,
+        _action: &A,
+        _reward: f64,
+        _next_state: Option<&S>,
+        _rng: &mut dyn RngCore,
+    ) {
+    }
+
+    fn set_greedy(&mut self, _greedy: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::policy::UniformRandomPolicy;
+    use crate::env::grid_world::{GridWorld, GridWorldAction};
+
+    /// Endless counter: every action moves s to s + 1 with reward 1 (plus `bonus` for action 0)
+    struct Counter {
+        bonus: f64,
+    }
+
+    impl MarkovDecisionProcess<usize, usize> for Counter {
+        fn states(&self) -> Vec<usize> {
+            vec![]
+        }
+
+        fn actions(&self) -> Vec<usize> {
+            (0..4).collect()
+        }
+
+        fn actions_at(&self, _state: &usize) -> Vec<usize> {
+            self.actions()
+        }
+
+        fn reward(&self, _state: &usize, action: &usize) -> f64 {
+            if *action == 0 {
+                1.0 + self.bonus
+            } else {
+                1.0
+            }
+        }
+
+        fn transition(&self, state: &usize, _action: &usize) -> Option<usize> {
+            Some(state + 1)
+        }
+    }
+
+    #[test]
+    fn root_actions_are_tried_before_ucb() {
+        let env = Counter { bonus: 9.0 };
+        let mut rng = StdRng::seed_from_u64(0);
+        // No exploration term: after the untried actions, only the mean return counts
+        let stats = |num_simulations| {
+            let planner = MCTS::new(UniformRandomPolicy::new(&env), 0.0, num_simulations, 1, 1.0);
+            planner
+                .search(&env, &0, &mut StdRng::seed_from_u64(0))
+                .get_action_stats()
+                .iter()
+                .map(|(_, n, _)| *n)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(stats(4), vec![1, 1, 1, 1]);
+        assert_eq!(stats(6), vec![3, 1, 1, 1]);
+
+        let planner = MCTS::new(UniformRandomPolicy::new(&env), 0.0, 4, 1, 1.0);
+        let result = planner.search(&env, &0, &mut rng);
+        assert_eq!(result.get_best_action(), Some(&0));
+    }
+
+    #[test]
+    fn visit_counts_sum_to_num_simulations() {
+        let env = Counter { bonus: 0.5 };
+        let planner = MCTS::new(UniformRandomPolicy::new(&env), 1.0, 250, 6, 0.9);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tree = vec![planner.new_node(&env, 0)];
+        for _ in 0..250 {
+            planner.simulate(&env, &mut tree, 0, 0, &mut rng);
+        }
+        assert_eq!(tree[0].visits, 250);
+        assert_eq!(tree[0].edges.iter().map(|e| e.visits).sum::<usize>(), 250);
+
+        let result = planner.search(&env, &0, &mut rng);
+        let total: usize = result.get_action_stats().iter().map(|(_, n, _)| n).sum();
+        assert_eq!(total, 250);
+    }
+
+    #[test]
+    fn max_depth_stops_descent_and_rollout() {
+        let env = Counter { bonus: 0.0 };
+        let max_depth = 3;
+        let planner = MCTS::new(UniformRandomPolicy::new(&env), 1.0, 200, max_depth, 1.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tree = vec![planner.new_node(&env, 0)];
+        for _ in 0..200 {
+            // Tree steps plus rollout steps add up to exactly `max_depth` rewards of 1
+            let g = planner.simulate(&env, &mut tree, 0, 0, &mut rng);
+            assert_eq!(g, max_depth as f64);
+        }
+        // Nodes at `max_depth` may be created but are never expanded
+        for node in tree.iter() {
+            assert!(node.state <= max_depth);
+            if node.state == max_depth {
+                assert_eq!(node.visits, 0);
+            }
+        }
+        assert!(tree
+            .iter()
+            .any(|node| node.state == max_depth - 1 && node.visits > 1));
+    }
+
+    #[test]
+    fn search_heads_for_the_goal() {
+        // Corridor: the goal is two cells to the right, every other move earns nothing or bumps
+        let env = GridWorld::with_walls(4, 1, (1, 0), (3, 0), vec![]);
+        let planner = MCTS::new(UniformRandomPolicy::new(&env), 1.0, 500, 10, 0.9);
+        let mut rng = StdRng::seed_from_u64(42);
+        let result = planner.search(&env, &(1, 0), &mut rng);
+        assert_eq!(result.get_best_action(), Some(&GridWorldAction::Right));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod dynamic_programming;
+pub mod mcts;
+pub mod model;
+pub mod prioritized_sweeping;
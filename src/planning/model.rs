@@ -0,0 +1,166 @@
+use crate::base::process::MarkovDecisionProcess;
+use std::collections::{HashMap, HashSet};
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Tabular Model
+// └──────────────────────────────────────────────────────────┘
+/// Sample model learned from real transitions
+///
+/// For every tried `(s, a)` the observed outcomes `(next_state, count, reward_sum)` are kept,
+/// so `transition_probs` is the empirical next-state distribution with the mean reward per
+/// outcome. A deterministic model (`TabularModel::deterministic`) only keeps the latest
+/// outcome, which is what Dyna-Q assumes and what lets it track a changing environment.
+///
+/// Untried pairs are reported as terminating with reward 0.
+#[derive(Debug, Clone)]
+#[allow(clippy::type_complexity)]
+pub struct TabularModel<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    table: HashMap<S, Vec<(A, Vec<(Option<S>, usize, f64)>)>>,
+    pairs: Vec<(S, A)>,
+    _deterministic: bool,
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> Default for TabularModel<S, A> {
+    fn default() -> Self {
+        TabularModel::new()
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> TabularModel<S, A> {
+    pub fn new() -> Self {
+        TabularModel {
+            table: HashMap::new(),
+            pairs: vec![],
+            _deterministic: false,
+        }
+    }
+
+    pub fn deterministic() -> Self {
+        TabularModel {
+            table: HashMap::new(),
+            pairs: vec![],
+            _deterministic: true,
+        }
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self._deterministic
+    }
+
+    /// Record a real transition (`None` marks a terminal next state)
+    pub fn update(&mut self, state: &S, action: &A, reward: f64, next_state: Option<&S>) {
+        let entries = self.table.entry(state.clone()).or_default();
+        let outcomes = match entries.iter_mut().position(|(a, _)| a == action) {
+            Some(i) => &mut entries[i].1,
+            None => {
+                self.pairs.push((state.clone(), action.clone()));
+                entries.push((action.clone(), vec![]));
+                &mut entries.last_mut().unwrap().1
+            }
+        };
+        if self._deterministic {
+            outcomes.clear();
+        }
+        match outcomes
+            .iter_mut()
+            .find(|(s_next, _, _)| s_next.as_ref() == next_state)
+        {
+            Some((_, count, reward_sum)) => {
+                *count += 1;
+                *reward_sum += reward;
+            }
+            None => outcomes.push((next_state.cloned(), 1, reward)),
+        }
+    }
+
+    /// Observed `(next_state, count, reward_sum)` for `(state, action)`
+    pub fn outcomes(&self, state: &S, action: &A) -> &[(Option<S>, usize, f64)] {
+        self.table
+            .get(state)
+            .and_then(|entries| entries.iter().find(|(a, _)| a == action))
+            .map(|(_, o)| o.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Number of times `(state, action)` has been recorded
+    pub fn get_count(&self, state: &S, action: &A) -> usize {
+        self.outcomes(state, action).iter().map(|(_, n, _)| n).sum()
+    }
+
+    pub fn is_known(&self, state: &S, action: &A) -> bool {
+        !self.outcomes(state, action).is_empty()
+    }
+
+    /// Every tried `(state, action)` pair, in the order first tried
+    pub fn observed_pairs(&self) -> &[(S, A)] {
+        &self.pairs
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> MarkovDecisionProcess<S, A>
+    for TabularModel<S, A>
+{
+    /// States seen either as a source or as a successor (sources first, in the order first tried)
+    fn states(&self) -> Vec<S> {
+        let mut seen = HashSet::new();
+        let mut states = vec![];
+        let successors = self
+            .pairs
+            .iter()
+            .flat_map(|(s, a)| self.outcomes(s, a).iter())
+            .filter_map(|(s_next, _, _)| s_next.as_ref());
+        for s in self.pairs.iter().map(|(s, _)| s).chain(successors) {
+            if seen.insert(s) {
+                states.push(s.clone());
+            }
+        }
+        states
+    }
+
+    fn actions(&self) -> Vec<A> {
+        let mut actions: Vec<A> = vec![];
+        for (_, a) in self.pairs.iter() {
+            if !actions.contains(a) {
+                actions.push(a.clone());
+            }
+        }
+        actions
+    }
+
+    fn actions_at(&self, state: &S) -> Vec<A> {
+        self.table
+            .get(state)
+            .map(|entries| entries.iter().map(|(a, _)| a.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Mean observed reward
+    fn reward(&self, state: &S, action: &A) -> f64 {
+        let outcomes = self.outcomes(state, action);
+        let n: usize = outcomes.iter().map(|(_, n, _)| n).sum();
+        if n == 0 {
+            return 0.0;
+        }
+        outcomes.iter().map(|(_, _, r)| r).sum::<f64>() / n as f64
+    }
+
+    /// Most frequent observed next state
+    fn transition(&self, state: &S, action: &A) -> Option<S> {
+        self.outcomes(state, action)
+            .iter()
+            .max_by_key(|(_, n, _)| *n)
+            .and_then(|(s_next, _, _)| s_next.clone())
+    }
+
+    fn transition_probs(&self, state: &S, action: &A) -> Vec<(Option<S>, f64, f64)> {
+        let outcomes = self.outcomes(state, action);
+        let n: usize = outcomes.iter().map(|(_, n, _)| n).sum();
+        if n == 0 {
+            return vec![(None, 1.0, 0.0)];
+        }
+        outcomes
+            .iter()
+            .map(|(s_next, k, r)| (s_next.clone(), *k as f64 / n as f64, r / *k as f64))
+            .collect()
+    }
+}
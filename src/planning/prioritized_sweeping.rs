@@ -0,0 +1,258 @@
+use crate::base::function::QTable;
+use crate::base::process::MarkovDecisionProcess;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Prioritized Sweeping
+// └──────────────────────────────────────────────────────────┘
+/// Prioritized sweeping over a tabular model
+///
+/// `(s, a)` pairs are queued with priority |Σ p(s', r | s, a) [r + γ max_a' Q(s', a')] - Q(s, a)|
+/// when it exceeds `theta`. Each `plan` call pops up to `n_planning` pairs, moves Q(s, a)
+/// toward the expected target with step size `alpha` and re-queues the predecessors of s.
+/// Predecessors are registered with `add_predecessor` (learned models) or `track_model`
+/// (given models).
+#[derive(Debug, Clone)]
+pub struct PrioritizedSweeping<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> {
+    gamma: f64,
+    alpha: f64,
+    theta: f64,
+    n_planning: usize,
+    queue: BinaryHeap<QueueEntry<S, A>>,
+    priorities: QTable<S, A>,
+    predecessors: HashMap<S, Vec<(S, A)>>,
+    _seq: usize,
+    _queued: usize,
+}
+
+/// Max-heap entry; equal priorities pop in insertion order
+#[derive(Debug, Clone)]
+struct QueueEntry<S, A> {
+    priority: f64,
+    seq: usize,
+    state: S,
+    action: A,
+}
+
+impl<S, A> PartialEq for QueueEntry<S, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S, A> Eq for QueueEntry<S, A> {}
+
+impl<S, A> PartialOrd for QueueEntry<S, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S, A> Ord for QueueEntry<S, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .total_cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<S: Eq + std::hash::Hash + Clone, A: Clone + PartialEq> PrioritizedSweeping<S, A> {
+    pub fn new(gamma: f64, alpha: f64, theta: f64, n_planning: usize) -> Self {
+        PrioritizedSweeping {
+            gamma,
+            alpha,
+            theta,
+            n_planning,
+            queue: BinaryHeap::new(),
+            priorities: QTable::new(),
+            predecessors: HashMap::new(),
+            _seq: 0,
+            _queued: 0,
+        }
+    }
+
+    /// Number of distinct pairs waiting in the queue
+    pub fn queue_len(&self) -> usize {
+        self._queued
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue_len() == 0
+    }
+
+    /// Current queued priority of `(state, action)` (0 if not queued)
+    pub fn get_priority(&self, state: &S, action: &A) -> f64 {
+        self.priorities.get_value(state, action).unwrap_or(0.0)
+    }
+
+    /// Record that `(state, action)` can lead to `next_state`
+    pub fn add_predecessor(&mut self, state: &S, action: &A, next_state: &S) {
+        let entries = self.predecessors.entry(next_state.clone()).or_default();
+        if !entries.iter().any(|(s, a)| s == state && a == action) {
+            entries.push((state.clone(), action.clone()));
+        }
+    }
+
+    /// Register every predecessor of a given model
+    pub fn track_model<M: MarkovDecisionProcess<S, A>>(&mut self, mdp: &M) {
+        for s in mdp.states() {
+            for a in mdp.actions_at(&s) {
+                for (s_next, p, _) in mdp.transition_probs(&s, &a) {
+                    if let Some(s_next) = s_next.filter(|_| p > 0f64) {
+                        self.add_predecessor(&s, &a, &s_next);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn get_predecessors(&self, state: &S) -> &[(S, A)] {
+        self.predecessors
+            .get(state)
+            .map(|p| p.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Σ p(s', r | s, a) [r + γ max_a' Q(s', a')]
+    pub fn expected_target<M: MarkovDecisionProcess<S, A>>(
+        &self,
+        mdp: &M,
+        q_table: &QTable<S, A>,
+        state: &S,
+        action: &A,
+    ) -> f64 {
+        mdp.transition_probs(state, action)
+            .into_iter()
+            .map(|(s_next, p, r)| {
+                let v_next = s_next.map_or(0f64, |s| q_table.max_value(&s));
+                p * (r + self.gamma * v_next)
+            })
+            .sum()
+    }
+
+    /// Bellman error magnitude of `(state, action)`
+    pub fn bellman_error<M: MarkovDecisionProcess<S, A>>(
+        &self,
+        mdp: &M,
+        q_table: &QTable<S, A>,
+        state: &S,
+        action: &A,
+    ) -> f64 {
+        let q = q_table.get_value(state, action).unwrap_or(0.0);
+        (self.expected_target(mdp, q_table, state, action) - q).abs()
+    }
+
+    /// Queue `(state, action)` if `priority > theta`, keeping the larger priority if queued
+    pub fn push(&mut self, state: &S, action: &A, priority: f64) {
+        let old = self.get_priority(state, action);
+        if priority <= self.theta || priority <= old {
+            return;
+        }
+        if old == 0f64 {
+            self._queued += 1;
+        }
+        self.priorities.update_value(state, action, priority);
+        self._seq += 1;
+        self.queue.push(QueueEntry {
+            priority,
+            seq: self._seq,
+            state: state.clone(),
+            action: action.clone(),
+        });
+    }
+
+    /// Pop the pair with the largest priority
+    pub fn pop(&mut self) -> Option<(S, A)> {
+        while let Some(entry) = self.queue.pop() {
+            // Skip entries superseded by a later push with higher priority
+            if self.get_priority(&entry.state, &entry.action) == entry.priority {
+                self.priorities
+                    .update_value(&entry.state, &entry.action, 0f64);
+                self._queued -= 1;
+                return Some((entry.state, entry.action));
+            }
+        }
+        None
+    }
+
+    /// Queue every `(s, a)` of `mdp` by its Bellman error
+    pub fn push_all<M: MarkovDecisionProcess<S, A>>(&mut self, mdp: &M, q_table: &QTable<S, A>) {
+        for s in mdp.states() {
+            for a in mdp.actions_at(&s) {
+                let priority = self.bellman_error(mdp, q_table, &s, &a);
+                self.push(&s, &a, priority);
+            }
+        }
+    }
+
+    /// Up to `n_planning` prioritized updates; returns the number performed
+    pub fn plan<M: MarkovDecisionProcess<S, A>>(
+        &mut self,
+        mdp: &M,
+        q_table: &mut QTable<S, A>,
+    ) -> usize {
+        let mut updates = 0;
+        while updates < self.n_planning {
+            let Some((s, a)) = self.pop() else {
+                break;
+            };
+            let q = q_table.get_value(&s, &a).unwrap_or(0.0);
+            let target = self.expected_target(mdp, q_table, &s, &a);
+            q_table.update_value(&s, &a, q + self.alpha * (target - q));
+            updates += 1;
+
+            for (s_bar, a_bar) in self.get_predecessors(&s).to_vec() {
+                let priority = self.bellman_error(mdp, q_table, &s_bar, &a_bar);
+                self.push(&s_bar, &a_bar, priority);
+            }
+        }
+        updates
+    }
+
+    /// Plan on a given model in batches of `n_planning` until the queue is empty or at least
+    /// `max_updates` updates were made
+    ///
+    /// Returns the Q-table and the number of updates performed.
+    pub fn solve<M: MarkovDecisionProcess<S, A>>(
+        &mut self,
+        mdp: &M,
+        max_updates: usize,
+    ) -> (QTable<S, A>, usize) {
+        let mut q_table = QTable::from_mdp(mdp, 0f64);
+        self.track_model(mdp);
+        self.push_all(mdp, &q_table);
+
+        let mut updates = 0;
+        while updates < max_updates {
+            let n = self.plan(mdp, &mut q_table);
+            if n == 0 {
+                break;
+            }
+            updates += n;
+        }
+        (q_table, updates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_len_counts_distinct_pairs() {
+        let mut sweeping: PrioritizedSweeping<usize, usize> =
+            PrioritizedSweeping::new(1.0, 1.0, 0.1, 10);
+        sweeping.push(&0, &0, 1.0);
+        sweeping.push(&0, &0, 2.0);
+        sweeping.push(&1, &0, 0.05);
+        sweeping.push(&1, &1, 0.5);
+        assert_eq!(sweeping.queue_len(), 2);
+
+        assert_eq!(sweeping.pop(), Some((0, 0)));
+        assert_eq!(sweeping.queue_len(), 1);
+        assert_eq!(sweeping.pop(), Some((1, 1)));
+        assert_eq!(sweeping.pop(), None);
+        assert!(sweeping.is_empty());
+    }
+}